@@ -0,0 +1,352 @@
+use crate::{clip_line_liang_barsky, from_u32_rgb, from_u8_rgb, Image, Point, Rect, BLACK, WHITE};
+use std::collections::HashMap;
+
+/// How a coverage-weighted color is combined with the pixel already in the image.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Composite {
+    /// Blends the color over the existing pixel by coverage.
+    Over,
+    /// Keeps the darker of the existing pixel and the color blended over white, channel by
+    /// channel. Crossing dark strokes then don't leave lighter seams where they overlap.
+    Darken,
+    /// Keeps the lighter of the existing pixel and the color blended over black.
+    Lighten,
+}
+
+fn lerp(from: u32, to: u32, coverage: f64) -> (f64, f64, f64) {
+    let (fr, fg, fb) = from_u32_rgb(from);
+    let (tr, tg, tb) = from_u32_rgb(to);
+    let mix = |f: u8, t: u8| f as f64 + (t as f64 - f as f64) * coverage;
+    (mix(fr, tr), mix(fg, tg), mix(fb, tb))
+}
+
+fn fpart(v: f64) -> f64 {
+    v - v.floor()
+}
+
+/// Per-pixel coverage of a shape. Pixels touched more than once keep their highest coverage,
+/// so symmetric points and the joints between segments aren't blended twice.
+struct Coverage {
+    pixels: HashMap<Point, f64>,
+    /// Lines are clipped to this before they're stepped through.
+    window: Rect,
+}
+
+impl Coverage {
+    /// Coverage to be drawn into `image`. The window is a couple of pixels larger than its
+    /// `clip_rect`, so that wherever a line is cut, the pixels straddling it and its faded end
+    /// are outside the visible area.
+    fn new(image: &Image) -> Self {
+        let rect = image.clip_rect();
+        let window = if rect.width == 0 || rect.height == 0 {
+            rect
+        } else {
+            Rect {
+                x: rect.x - 2,
+                y: rect.y - 2,
+                width: rect.width + 4,
+                height: rect.height + 4,
+            }
+        };
+        Self {
+            pixels: HashMap::new(),
+            window,
+        }
+    }
+
+    fn add(&mut self, x: i64, y: i64, coverage: f64) {
+        if coverage <= 0. {
+            return;
+        }
+        let c = self.pixels.entry((x, y)).or_insert(0.);
+        *c = c.max(coverage.min(1.));
+    }
+
+    /// Xiaolin Wu's line between two points with sub-pixel precision. Unless `full_ends` is
+    /// set, the end pixels are weighted by how much of them the line covers horizontally.
+    fn line(&mut self, a: (f64, f64), b: (f64, f64), full_ends: bool) {
+        let ((mut x0, mut y0), (mut x1, mut y1)) = match clip_line_liang_barsky(a, b, &self.window)
+        {
+            Some(segment) => segment,
+            None => return,
+        };
+        let steep = (y1 - y0).abs() > (x1 - x0).abs();
+        if steep {
+            std::mem::swap(&mut x0, &mut y0);
+            std::mem::swap(&mut x1, &mut y1);
+        }
+        if x0 > x1 {
+            std::mem::swap(&mut x0, &mut x1);
+            std::mem::swap(&mut y0, &mut y1);
+        }
+        let dx = x1 - x0;
+        let gradient = if dx == 0. { 1. } else { (y1 - y0) / dx };
+        let mut plot = |x: f64, y: f64, c: f64| {
+            let (x, y) = (x as i64, y as i64);
+            if steep {
+                self.add(y, x, c);
+            } else {
+                self.add(x, y, c);
+            }
+        };
+
+        let xpxl1 = x0.round();
+        let y_end = y0 + gradient * (xpxl1 - x0);
+        let x_gap = if full_ends { 1. } else { 1. - fpart(x0 + 0.5) };
+        plot(xpxl1, y_end.floor(), (1. - fpart(y_end)) * x_gap);
+        plot(xpxl1, y_end.floor() + 1., fpart(y_end) * x_gap);
+        let mut intery = y_end + gradient;
+
+        let xpxl2 = x1.round();
+        let y_end = y1 + gradient * (xpxl2 - x1);
+        let x_gap = if full_ends { 1. } else { fpart(x1 + 0.5) };
+        plot(xpxl2, y_end.floor(), (1. - fpart(y_end)) * x_gap);
+        plot(xpxl2, y_end.floor() + 1., fpart(y_end) * x_gap);
+
+        let mut x = xpxl1 + 1.;
+        while x < xpxl2 {
+            plot(x, intery.floor(), 1. - fpart(intery));
+            plot(x, intery.floor() + 1., fpart(intery));
+            intery += gradient;
+            x += 1.;
+        }
+    }
+
+    /// Wu-style ellipse: each octant is stepped along its major axis and the two pixels
+    /// straddling the exact curve share the coverage.
+    fn ellipse(&mut self, (xm, ym): Point, (a, b): (f64, f64)) {
+        if a <= 0. || b <= 0. {
+            self.add(xm, ym, 1.);
+            return;
+        }
+        let mut plot4 = |x: i64, y: i64, c: f64| {
+            for (sx, sy) in [(1, 1), (-1, 1), (1, -1), (-1, -1)] {
+                self.add(xm + sx * x, ym + sy * y, c);
+            }
+        };
+        let diagonal = (a * a + b * b).sqrt();
+        let x_limit = (a * a / diagonal).ceil() as i64;
+        for x in 0..=x_limit {
+            let y = b * (1. - (x as f64 / a).powi(2)).max(0.).sqrt();
+            plot4(x, y.floor() as i64, 1. - fpart(y));
+            plot4(x, y.floor() as i64 + 1, fpart(y));
+        }
+        let y_limit = (b * b / diagonal).ceil() as i64;
+        for y in 0..=y_limit {
+            let x = a * (1. - (y as f64 / b).powi(2)).max(0.).sqrt();
+            plot4(x.floor() as i64, y, 1. - fpart(x));
+            plot4(x.floor() as i64 + 1, y, fpart(x));
+        }
+    }
+
+    /// Flattens the Bezier curve with control points `points` into line segments about two
+    /// pixels long. Segment ends are drawn at full coverage so the joints don't fade.
+    fn bezier(&mut self, points: &[(f64, f64)]) {
+        let length = points
+            .windows(2)
+            .map(|w| ((w[1].0 - w[0].0).powi(2) + (w[1].1 - w[0].1).powi(2)).sqrt())
+            .sum::<f64>();
+        let steps = ((length / 2.).ceil() as usize).max(1);
+        let mut prev = points[0];
+        for i in 1..=steps {
+            let t = i as f64 / steps as f64;
+            let mut p = points.to_vec();
+            while p.len() > 1 {
+                p = p
+                    .windows(2)
+                    .map(|w| {
+                        (
+                            (1. - t) * w[0].0 + t * w[1].0,
+                            (1. - t) * w[0].1 + t * w[1].1,
+                        )
+                    })
+                    .collect();
+            }
+            self.line(prev, p[0], true);
+            prev = p[0];
+        }
+    }
+
+    fn composite(self, image: &mut Image, color: u32, mode: Composite) {
+        for ((x, y), coverage) in self.pixels {
+            image.plot_aa(x, y, coverage, color, mode);
+        }
+    }
+}
+
+impl Image {
+    /// Plots `color` with `coverage` in `0.0..=1.0` at `(x, y)`, combined with the existing
+    /// pixel according to `mode`.
+    pub fn plot_aa(&mut self, x: i64, y: i64, coverage: f64, color: u32, mode: Composite) {
+        let dst = match self.get(x, y) {
+            Some(dst) => dst,
+            None => return,
+        };
+        let coverage = coverage.clamp(0., 1.);
+        let (r, g, b) = match mode {
+            Composite::Over => lerp(dst, color, coverage),
+            Composite::Darken | Composite::Lighten => {
+                let (dr, dg, db) = from_u32_rgb(dst);
+                let base = if mode == Composite::Darken {
+                    WHITE
+                } else {
+                    BLACK
+                };
+                let (r, g, b) = lerp(base, color, coverage);
+                let pick = |d: u8, s: f64| {
+                    if mode == Composite::Darken {
+                        s.min(d as f64)
+                    } else {
+                        s.max(d as f64)
+                    }
+                };
+                (pick(dr, r), pick(dg, g), pick(db, b))
+            }
+        };
+        self.plot_color(
+            x,
+            y,
+            Some(from_u8_rgb(
+                r.round() as u8,
+                g.round() as u8,
+                b.round() as u8,
+            )),
+        );
+    }
+
+    pub fn plot_line_aa(&mut self, a: (f64, f64), b: (f64, f64), color: u32, mode: Composite) {
+        let mut coverage = Coverage::new(self);
+        coverage.line(a, b, false);
+        coverage.composite(self, color, mode);
+    }
+
+    pub fn plot_circle_aa(&mut self, center: Point, r: f64, color: u32, mode: Composite) {
+        self.plot_ellipse_aa(center, (r, r), color, mode)
+    }
+
+    pub fn plot_ellipse_aa(
+        &mut self,
+        center: Point,
+        (a, b): (f64, f64),
+        color: u32,
+        mode: Composite,
+    ) {
+        let mut coverage = Coverage::new(self);
+        coverage.ellipse(center, (a, b));
+        coverage.composite(self, color, mode);
+    }
+
+    pub fn plot_quad_bezier_aa(
+        &mut self,
+        p0: (f64, f64),
+        p1: (f64, f64),
+        p2: (f64, f64),
+        color: u32,
+        mode: Composite,
+    ) {
+        let mut coverage = Coverage::new(self);
+        coverage.bezier(&[p0, p1, p2]);
+        coverage.composite(self, color, mode);
+    }
+
+    pub fn plot_cubic_bezier_aa(
+        &mut self,
+        p0: (f64, f64),
+        p1: (f64, f64),
+        p2: (f64, f64),
+        p3: (f64, f64),
+        color: u32,
+        mode: Composite,
+    ) {
+        let mut coverage = Coverage::new(self);
+        coverage.bezier(&[p0, p1, p2, p3]);
+        coverage.composite(self, color, mode);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RED;
+
+    fn gray(image: &Image, x: i64, y: i64) -> u8 {
+        from_u32_rgb(image.get(x, y).unwrap()).0
+    }
+
+    #[test]
+    fn horizontal_lines() {
+        let mut image = Image::new(10, 6, 0, 0);
+        image.plot_line_aa((1., 2.), (8., 2.), BLACK, Composite::Over);
+        for x in 2..8 {
+            assert_eq!(image.get(x, 2), Some(BLACK));
+            assert_eq!(
+                (image.get(x, 1), image.get(x, 3)),
+                (Some(WHITE), Some(WHITE))
+            );
+        }
+        // The end pixels are only half covered.
+        assert_eq!((gray(&image, 1, 2), gray(&image, 8, 2)), (128, 128));
+
+        // Halfway between two rows, both get half the coverage.
+        let mut image = Image::new(10, 6, 0, 0);
+        image.plot_line_aa((0., 2.5), (9., 2.5), BLACK, Composite::Over);
+        for x in 1..9 {
+            assert_eq!((gray(&image, x, 2), gray(&image, x, 3)), (128, 128));
+        }
+    }
+
+    #[test]
+    fn symmetric_circle() {
+        let mut image = Image::new(21, 21, 0, 0);
+        image.plot_circle_aa((10, 10), 6.3, BLACK, Composite::Over);
+        assert!(image.bytes.iter().any(|p| *p != WHITE));
+        for y in 0..21 {
+            for x in 0..21 {
+                let p = image.get(x, y);
+                assert_eq!(p, image.get(20 - x, y));
+                assert_eq!(p, image.get(x, 20 - y));
+                assert_eq!(p, image.get(y, x));
+            }
+        }
+    }
+
+    #[test]
+    fn darken_keeps_the_darker_stroke() {
+        type Segment = ((f64, f64), (f64, f64));
+        let (a, b) = (((0., 1.3), (15., 12.1)), ((0., 11.6), (15., 2.2)));
+        let draw = |lines: &[Segment]| {
+            let mut image = Image::new(16, 14, 0, 0);
+            for (p, q) in lines {
+                image.plot_line_aa(*p, *q, RED, Composite::Darken);
+            }
+            image
+        };
+        let (only_a, only_b, both) = (draw(&[a]), draw(&[b]), draw(&[a, b]));
+        for i in 0..both.bytes.len() {
+            let (pa, pb) = (from_u32_rgb(only_a.bytes[i]), from_u32_rgb(only_b.bytes[i]));
+            let darker = from_u8_rgb(pa.0.min(pb.0), pa.1.min(pb.1), pa.2.min(pb.2));
+            assert_eq!(both.bytes[i], darker);
+        }
+    }
+
+    #[test]
+    fn clipped_line() {
+        let draw = |a: (f64, f64), b: (f64, f64)| {
+            let mut image = Image::new(20, 20, 0, 0);
+            image.plot_line_aa(a, b, BLACK, Composite::Over);
+            image.bytes
+        };
+        let (a, b) = ((-10., 3.2), (30., 15.7));
+        let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+        let far = 1e8;
+        let extended = draw(
+            (a.0 - far * dx, a.1 - far * dy),
+            (b.0 + far * dx, b.1 + far * dy),
+        );
+        assert!(extended.iter().any(|p| *p != WHITE));
+        for (p, q) in draw(a, b).into_iter().zip(extended) {
+            let (p, q) = (from_u32_rgb(p).0 as i32, from_u32_rgb(q).0 as i32);
+            assert!((p - q).abs() <= 1, "{} {}", p, q);
+        }
+    }
+}
@@ -0,0 +1,304 @@
+use crate::{BitmapFont, GlyphMetrics, Image};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::prelude::*;
+
+type Result<T> = std::result::Result<T, Box<dyn ::std::error::Error>>;
+
+/// Glyph cells per row of the sheet a BDF font is packed into.
+const SHEET_COLUMNS: usize = 32;
+
+struct BdfGlyph {
+    encoding: Option<u32>,
+    metrics: GlyphMetrics,
+    rows: Vec<Vec<u8>>,
+}
+
+fn numbers<const N: usize>(line_no: usize, args: &[&str]) -> Result<[i64; N]> {
+    let mut ret = [0; N];
+    if args.len() < N {
+        return Err(format!(
+            "bdf:{}: expected {} numbers, found {}",
+            line_no,
+            N,
+            args.len()
+        )
+        .into());
+    }
+    for (r, a) in ret.iter_mut().zip(args) {
+        *r = a
+            .parse::<i64>()
+            .map_err(|err| format!("bdf:{}: invalid number `{}`: {}", line_no, a, err))?;
+    }
+    Ok(ret)
+}
+
+/// The `ENCODING` values below which a font's charset agrees with Unicode, going by its
+/// `CHARSET_REGISTRY` and `CHARSET_ENCODING` properties. Fonts without them are taken to be
+/// Unicode, and the other ISO 8859 parts and KOI8 only share their ASCII half with it.
+fn unicode_limit(registry: Option<&str>, encoding: Option<&str>) -> u32 {
+    let registry = match registry {
+        Some(r) => r.to_ascii_uppercase(),
+        None => return u32::MAX,
+    };
+    match (registry.as_str(), encoding) {
+        ("ISO10646", _) => u32::MAX,
+        ("ISO8859", Some("1")) => 0x100,
+        ("ISO8859" | "ISO646.1991" | "KOI8" | "ASCII", _) => 0x80,
+        _ => 0,
+    }
+}
+
+/// A property value, without the quotes around strings.
+fn property(args: &[&str]) -> String {
+    args.join(" ").trim_matches('"').to_string()
+}
+
+impl BitmapFont {
+    pub fn from_bdf(path: &str) -> Result<Self> {
+        let mut file = File::open(path)?;
+        let mut s = String::new();
+        file.read_to_string(&mut s)?;
+        Self::from_bdf_str(&s)
+    }
+
+    /// Loads a Glyph Bitmap Distribution Format (BDF 2.1) font. Glyphs are packed into a sheet
+    /// of cells as big as the font bounding box, and every glyph whose `ENCODING` is a Unicode
+    /// code point under the font's `CHARSET_REGISTRY` is added to the font's `encoding` map.
+    /// Glyphs of other charsets stay in the sheet, unmapped.
+    pub fn from_bdf_str(s: &str) -> Result<Self> {
+        let mut lines = s.lines().enumerate().map(|(i, l)| (i + 1, l));
+        let mut font_bbx = None;
+        let mut font_dwidth = None;
+        let (mut ascent, mut descent, mut default_char) = (None, None, None);
+        let (mut registry, mut charset_encoding) = (None, None);
+        let mut glyphs: Vec<BdfGlyph> = vec![];
+        let mut current: Option<BdfGlyph> = None;
+        let mut seen_startfont = false;
+
+        while let Some((line_no, line)) = lines.next() {
+            let mut words = line.split_whitespace();
+            let keyword = match words.next() {
+                Some(k) => k,
+                None => continue,
+            };
+            let args = words.collect::<Vec<&str>>();
+            if !seen_startfont {
+                if keyword != "STARTFONT" {
+                    return Err(format!("bdf:{}: expected STARTFONT", line_no).into());
+                }
+                seen_startfont = true;
+                continue;
+            }
+            match keyword {
+                "FONTBOUNDINGBOX" => font_bbx = Some(numbers::<4>(line_no, &args)?),
+                "FONT_ASCENT" => ascent = Some(numbers::<1>(line_no, &args)?[0]),
+                "FONT_DESCENT" => descent = Some(numbers::<1>(line_no, &args)?[0]),
+                "DEFAULT_CHAR" => {
+                    default_char = u32::try_from(numbers::<1>(line_no, &args)?[0]).ok()
+                }
+                "CHARSET_REGISTRY" => registry = Some(property(&args)),
+                "CHARSET_ENCODING" => charset_encoding = Some(property(&args)),
+                "DWIDTH" if current.is_none() => {
+                    font_dwidth = Some(numbers::<2>(line_no, &args)?[0]);
+                }
+                "STARTCHAR" => {
+                    let [w, h, xo, yo] = font_bbx.ok_or_else(|| {
+                        format!("bdf:{}: STARTCHAR before FONTBOUNDINGBOX", line_no)
+                    })?;
+                    current = Some(BdfGlyph {
+                        encoding: None,
+                        metrics: GlyphMetrics {
+                            width: w.max(0) as usize,
+                            height: h.max(0) as usize,
+                            x_offset: xo,
+                            y_offset: yo,
+                            advance: font_dwidth.unwrap_or(w),
+                        },
+                        rows: vec![],
+                    });
+                }
+                "ENCODING" | "DWIDTH" | "BBX" | "BITMAP" | "ENDCHAR" => {
+                    let glyph = current.as_mut().ok_or_else(|| {
+                        format!("bdf:{}: {} outside of a glyph", line_no, keyword)
+                    })?;
+                    match keyword {
+                        "ENCODING" => {
+                            // `ENCODING -1 n` glyphs have no standard encoding.
+                            let [e] = numbers::<1>(line_no, &args)?;
+                            glyph.encoding = u32::try_from(e).ok();
+                        }
+                        "DWIDTH" => glyph.metrics.advance = numbers::<2>(line_no, &args)?[0],
+                        "BBX" => {
+                            let [w, h, xo, yo] = numbers::<4>(line_no, &args)?;
+                            if w < 0 || h < 0 {
+                                return Err(format!(
+                                    "bdf:{}: negative glyph bounding box",
+                                    line_no
+                                )
+                                .into());
+                            }
+                            glyph.metrics.width = w as usize;
+                            glyph.metrics.height = h as usize;
+                            glyph.metrics.x_offset = xo;
+                            glyph.metrics.y_offset = yo;
+                        }
+                        "BITMAP" => {
+                            for _ in 0..glyph.metrics.height {
+                                let (line_no, row) = lines.next().ok_or_else(|| {
+                                    format!("bdf:{}: glyph bitmap is cut short", line_no)
+                                })?;
+                                let row = row.trim();
+                                let hex = |c: u8| (c as char).to_digit(16);
+                                let bytes = row
+                                    .as_bytes()
+                                    .chunks_exact(2)
+                                    .map(|pair| match (hex(pair[0]), hex(pair[1])) {
+                                        (Some(high), Some(low)) => Some((high << 4 | low) as u8),
+                                        _ => None,
+                                    })
+                                    .collect::<Option<Vec<u8>>>()
+                                    .ok_or_else(|| {
+                                        format!("bdf:{}: invalid bitmap row `{}`", line_no, row)
+                                    })?;
+                                glyph.rows.push(bytes);
+                            }
+                        }
+                        _ => glyphs.push(current.take().unwrap()),
+                    }
+                }
+                "ENDFONT" => break,
+                _ => {}
+            }
+        }
+        if current.is_some() {
+            return Err("bdf: missing ENDCHAR".into());
+        }
+        let [fw, fh, _, fyo] = font_bbx.ok_or("bdf: font has no FONTBOUNDINGBOX")?;
+
+        // Cells have to fit every glyph, even those whose BBX is bigger than the font's.
+        let glyph_width = glyphs
+            .iter()
+            .map(|g| g.metrics.width)
+            .max()
+            .unwrap_or(0)
+            .max(fw.max(0) as usize)
+            .max(1);
+        let glyph_height = glyphs
+            .iter()
+            .map(|g| g.metrics.height)
+            .max()
+            .unwrap_or(0)
+            .max(fh.max(0) as usize)
+            .max(1);
+        let columns = SHEET_COLUMNS.min(glyphs.len()).max(1);
+        let rows = glyphs.len().div_ceil(columns).max(1);
+        let (width, height) = match (
+            columns.checked_mul(glyph_width),
+            rows.checked_mul(glyph_height),
+        ) {
+            (Some(w), Some(h)) if w.checked_mul(h).is_some() => (w, h),
+            _ => return Err("bdf: font bounding box is too large".into()),
+        };
+        let limit = unicode_limit(registry.as_deref(), charset_encoding.as_deref());
+        let to_char = |e: u32| if e < limit { char::from_u32(e) } else { None };
+        let mut image = Image::new(width, height, 0, 0);
+        let mut encoding = HashMap::new();
+        let mut metrics = Vec::with_capacity(glyphs.len());
+        for (i, glyph) in glyphs.iter().enumerate() {
+            let (cx, cy) = (
+                ((i % columns) * glyph_width) as i64,
+                ((i / columns) * glyph_height) as i64,
+            );
+            for (y, row) in glyph.rows.iter().enumerate() {
+                for x in 0..glyph.metrics.width.min(row.len() * 8) {
+                    if row[x / 8] & (0x80 >> (x % 8)) != 0 {
+                        image.plot(cx + x as i64, cy + y as i64);
+                    }
+                }
+            }
+            if let Some(c) = glyph.encoding.and_then(to_char) {
+                encoding.entry(c).or_insert(i);
+            }
+            metrics.push(glyph.metrics);
+        }
+
+        let mut ret = BitmapFont::new(image, (glyph_width, glyph_height), 0, 0);
+        ret.descent = descent.unwrap_or(-fyo).max(0) as usize;
+        ret.ascent = ascent.unwrap_or(fh + fyo).max(0) as usize;
+        ret.encoding = Some(encoding);
+        ret.metrics = metrics;
+        ret.default_char = default_char.and_then(to_char);
+        Ok(ret)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BLACK;
+
+    fn font(bitmap: &str) -> String {
+        format!(
+            "STARTFONT 2.1\nFONTBOUNDINGBOX 4 2 0 0\nSTARTCHAR A\nENCODING 65\n\
+             BBX 4 2 0 0\nBITMAP\n{}ENDCHAR\nENDFONT\n",
+            bitmap
+        )
+    }
+
+    #[test]
+    fn glyph() {
+        let font = BitmapFont::from_bdf_str(&font("90\n60\n")).unwrap();
+        let glyph = font.glyph('A').unwrap();
+        let black = glyph.bytes.iter().map(|p| *p == BLACK).collect::<Vec<_>>();
+        assert_eq!(black, [true, false, false, true, false, true, true, false]);
+    }
+
+    #[test]
+    fn charset_registry() {
+        let font = |properties: &str| {
+            BitmapFont::from_bdf_str(&format!(
+                "STARTFONT 2.1\nFONTBOUNDINGBOX 1 1 0 0\nSTARTPROPERTIES 2\n{}ENDPROPERTIES\n\
+                 STARTCHAR A\nENCODING 65\nBBX 1 1 0 0\nBITMAP\n80\nENDCHAR\n\
+                 STARTCHAR Agrave\nENCODING 192\nBBX 1 1 0 0\nBITMAP\n80\nENDCHAR\n\
+                 STARTCHAR smiley\nENCODING 9786\nBBX 1 1 0 0\nBITMAP\n80\nENDCHAR\nENDFONT\n",
+                properties
+            ))
+            .unwrap()
+        };
+        let mapped = |font: &BitmapFont| {
+            ['A', 'À', '☺'].map(|c| font.encoding.as_ref().unwrap().contains_key(&c))
+        };
+        assert_eq!(mapped(&font("")), [true, true, true]);
+        let unicode = font("CHARSET_REGISTRY \"ISO10646\"\nCHARSET_ENCODING \"1\"\n");
+        assert_eq!(mapped(&unicode), [true, true, true]);
+        let latin1 = font("CHARSET_REGISTRY \"ISO8859\"\nCHARSET_ENCODING \"1\"\n");
+        assert_eq!(mapped(&latin1), [true, true, false]);
+        let cyrillic = font("CHARSET_REGISTRY \"ISO8859\"\nCHARSET_ENCODING \"5\"\n");
+        assert_eq!(mapped(&cyrillic), [true, false, false]);
+        let kanji = font("CHARSET_REGISTRY \"JISX0208.1983\"\nCHARSET_ENCODING \"0\"\n");
+        assert_eq!(mapped(&kanji), [false, false, false]);
+    }
+
+    #[test]
+    fn malformed() {
+        for bitmap in ["9G\n60\n", "é0\n60\n", "0é\n60\n", "+f\n60\n", "90\n"] {
+            assert!(
+                BitmapFont::from_bdf_str(&font(bitmap)).is_err(),
+                "{}",
+                bitmap
+            );
+        }
+        assert!(BitmapFont::from_bdf_str("FONTBOUNDINGBOX 1 1 0 0\n").is_err());
+        assert!(BitmapFont::from_bdf_str("STARTFONT 2.1\nSTARTCHAR A\n").is_err());
+        let unterminated = "STARTFONT 2.1\nFONTBOUNDINGBOX 1 1 0 0\nSTARTCHAR A\nENDFONT\n";
+        assert!(BitmapFont::from_bdf_str(unterminated).is_err());
+    }
+
+    #[test]
+    fn oversize_dimensions() {
+        let huge = "STARTFONT 2.1\nFONTBOUNDINGBOX 9223372036854775807 9223372036854775807 0 0\n\
+                    STARTCHAR A\nBBX 1 1 0 0\nBITMAP\n80\nENDCHAR\nENDFONT\n";
+        assert!(BitmapFont::from_bdf_str(huge).is_err());
+    }
+}
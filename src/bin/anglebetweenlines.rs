@@ -5,38 +5,6 @@ const WINDOW_WIDTH: usize = 300;
 const WINDOW_HEIGHT: usize = 300;
 include!("../bizcat.xbm.rs");
 
-fn find_angle((a1, b1, _c1): (i64, i64, i64), (a2, b2, _c2): (i64, i64, i64)) -> f64 {
-    let nom = (a1 * a2 + b1 * b2) as f64;
-    let denom = ((a1 * a1 + b1 * b1) * (a2 * a2 + b2 * b2)) as f64;
-
-    f64::acos(nom / f64::sqrt(denom))
-}
-
-fn find_line(point_a: Point, point_b: Point) -> (i64, i64, i64) {
-    let (xa, ya) = point_a;
-    let (xb, yb) = point_b;
-    let a = ya - yb;
-    let b = xb - xa;
-    let c = xa * yb - xb * ya;
-
-    (a, b, c)
-}
-
-fn plot_line(image: &mut Image, (a, b, c): (i64, i64, i64)) {
-    let x = if a != 0 { -c / a } else { 0 };
-    let mut prev_point = (x, 0);
-    for y in 0..(WINDOW_HEIGHT as i64) {
-        // ax+by+c =0 =>
-        // x=(-c-by)/a
-
-        let x = if a != 0 { -(c + b * y) / a } else { 0 };
-        let new_point = (x, y);
-        image.plot_line_width(prev_point, new_point, 1.0);
-        prev_point = new_point;
-        //image.plot(x, y);
-    }
-}
-
 fn main() {
     let mut bizcat = Image::new(BIZCAT_WIDTH, BIZCAT_HEIGHT, 0, 0);
     bizcat.bytes = bits_to_bytes(BIZCAT_BITS, BIZCAT_WIDTH);
@@ -115,15 +83,17 @@ fn main() {
 
         let l1 = find_line(points[0], points[1]);
         let l2 = find_line(points[2], points[3]);
-        plot_line(&mut image, l1);
-        plot_line(&mut image, l2);
+        for l in [l1, l2].into_iter().flatten() {
+            image.plot_line(l, 1.0);
+        }
 
-        let degrees = 57.2958 * find_angle(l1, l2);
-        image.write_str(
-            &bizcat,
-            &format!("~{:.2}\u{00a9}", degrees),
-            (WINDOW_WIDTH as i64 / 2, WINDOW_HEIGHT as i64 / 2),
-        );
+        if let Some(angle) = l1.zip(l2).and_then(|(l1, l2)| find_angle(l1, l2)) {
+            image.write_str(
+                &bizcat,
+                &format!("~{:.2}\u{00a9}", 57.2958 * angle),
+                (WINDOW_WIDTH as i64 / 2, WINDOW_HEIGHT as i64 / 2),
+            );
+        }
 
         image.draw(&mut buffer, BLACK, None, WINDOW_WIDTH);
 
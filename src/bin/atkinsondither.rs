@@ -26,8 +26,8 @@ fn atkinson(image: &mut Image) {
 }
 
 fn main() {
-    const INPUT_FILE: &str = "./figures/peppers.png";
-    let mut image = Image::magick_open(INPUT_FILE, 0, 0).unwrap();
+    const INPUT_FILE: &str = "./testimages/peppers.ppm";
+    let mut image = Image::from_netpbm(INPUT_FILE, 0, 0).unwrap();
     let width = image.width;
     let height = image.height;
     let mut buffer: Vec<u32> = vec![WHITE; width * height];
@@ -50,6 +50,9 @@ fn main() {
     window.limit_update_rate(Some(std::time::Duration::from_micros(16600)));
 
     atkinson(&mut image);
+    if let Some(output_file) = std::env::args().nth(1) {
+        image.save_pbm(&output_file, true).unwrap();
+    }
     image.draw_raw(&mut buffer, width);
     while window.is_open() && !window.is_key_down(Key::Escape) && !window.is_key_down(Key::Q) {
         window.update_with_buffer(&buffer, width, height).unwrap();
@@ -242,21 +242,18 @@ fn main() {
                 image.plot_square(*p, 3, 0.);
             }
         }
+        let stroke = Stroke {
+            cap: Cap::Round,
+            join: Join::Round,
+            ..Stroke::new(2.)
+        };
         for c in &curves {
-            let mut prev_point = c.points[0];
-            let mut sample = 0;
-            for t in (0..100).step_by(1) {
-                let t = (t as f64) / 100.;
-                if let Some(new_point) = c.get_point(t) {
-                    if sample == 0 {
-                        image.plot_line_width(prev_point, new_point, 2.);
-                        sample = 5;
-                        prev_point = new_point;
-                    }
-                    sample -= 1;
-                }
-            }
-            image.plot_line_width(prev_point, *c.points.last().unwrap(), 2.);
+            let mut points = (0..100)
+                .step_by(5)
+                .filter_map(|t| c.get_point((t as f64) / 100.))
+                .collect::<Vec<Point>>();
+            points.push(*c.points.last().unwrap());
+            image.stroke_polyline(&points, false, &stroke);
         }
         image.draw(&mut buffer, BLACK, None, WINDOW_WIDTH);
 
@@ -5,14 +5,6 @@ const WINDOW_WIDTH: usize = 400;
 const WINDOW_HEIGHT: usize = 400;
 include!("../bizcat.xbm.rs");
 
-pub fn distance_between_two_points(p_k: Point, p_l: Point) -> f64 {
-    let (x_k, y_k) = p_k;
-    let (x_l, y_l) = p_l;
-    let xlk = x_l - x_k;
-    let ylk = y_l - y_k;
-    f64::sqrt((xlk * xlk + ylk * ylk) as f64)
-}
-
 struct Bezier {
     points: Vec<Point>,
     weights: Vec<f64>,
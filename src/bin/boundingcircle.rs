@@ -8,14 +8,6 @@ include!("../me.xbm.rs");
 const WINDOW_WIDTH: usize = 400;
 const WINDOW_HEIGHT: usize = 400;
 
-pub fn distance_between_two_points(p_k: Point, p_l: Point) -> f64 {
-    let (x_k, y_k) = p_k;
-    let (x_l, y_l) = p_l;
-    let xlk = x_l - x_k;
-    let ylk = y_l - y_k;
-    f64::sqrt((xlk * xlk + ylk * ylk) as f64)
-}
-
 fn image_to_points(image: &Image) -> Vec<Point> {
     let mut ret = Vec::with_capacity(image.bytes.len());
     for y in 0..(image.height as i64) {
@@ -199,8 +191,19 @@ fn main() {
     //image.draw_outline();
 
     full.plot_circle((center.0 + 45, center.1 + 45), r as i64, 0.);
+
+    // The library's Welzl circle, in red, which keeps the center and radius unrounded.
+    let mut welzl = Image::new(WINDOW_WIDTH, WINDOW_HEIGHT, 0, 0);
+    let circle = image.min_enclosing_circle().unwrap();
+    let center = circle.center.round();
+    welzl.plot_circle(
+        (center.x + 45, center.y + 45),
+        circle.radius.ceil() as i64,
+        0.,
+    );
     image.draw(&mut buffer, BLACK, None, WINDOW_WIDTH);
     full.draw(&mut buffer, BLACK, None, WINDOW_WIDTH);
+    welzl.draw(&mut buffer, RED, None, WINDOW_WIDTH);
     while window.is_open() && !window.is_key_down(Key::Escape) && !window.is_key_down(Key::Q) {
         window
             .update_with_buffer(&buffer, WINDOW_WIDTH, WINDOW_HEIGHT)
@@ -1,14 +1,6 @@
 use bitmappers_companion::*;
 use minifb::{Key, Window, WindowOptions};
 
-pub fn distance_between_two_points(p_k: Point, p_l: Point) -> f64 {
-    let (x_k, y_k) = p_k;
-    let (x_l, y_l) = p_l;
-    let xlk = x_l - x_k;
-    let ylk = y_l - y_k;
-    f64::sqrt((xlk * xlk + ylk * ylk) as f64)
-}
-
 pub fn plot_line_width(_self: &mut Image, (x1, y1): (i64, i64), (x2, y2): (i64, i64), _wd: f64) {
     /* Bresenham's line algorithm */
     let mut d;
@@ -4,44 +4,6 @@ use minifb::{CursorStyle, Key, MouseButton, MouseMode, Window, WindowOptions};
 const WINDOW_WIDTH: usize = 300;
 const WINDOW_HEIGHT: usize = 300;
 
-fn perp_bisector((x_a, y_a): Point, (x_b, y_b): Point) -> (i64, i64, i64) {
-    let m_a = if x_b != y_b { (y_a - y_b) as f64 } else { -1.0 };
-    let m_b = (x_b - x_a) as f64;
-
-    let (x_m, y_m) = ((x_b + x_a) as f64 / 2.0, (y_b + y_a) as f64 / 2.0);
-    // slope form y=mx+b
-    // m_og = (y_m - y_n / x_m - x_n)
-    // m_og * m = -1 => m = (x_n - x_m) / (y_m - y_n) = m_b / m_a
-    //
-    // y = mx+b => y_m = m*x_m + b => b = y_m - m * x_m
-    //
-    // slope form y=mx+b -> implicit form αx+βy=γ
-    // y = m*x + y_m - m* x_m
-    (
-        m_b as i64,
-        -m_a as i64,
-        (((y_m * m_a) - (m_b * x_m)) as i64),
-    )
-}
-
-fn find_intersection((a1, b1, c1): (i64, i64, i64), (a2, b2, c2): (i64, i64, i64)) -> Point {
-    let denom = a1 * b2 - a2 * b1;
-
-    if denom == 0 {
-        return (0, 0);
-    }
-
-    ((b1 * c2 - b2 * c1) / denom, (a2 * c1 - a1 * c2) / denom)
-}
-
-pub fn distance_between_two_points(p_k: Point, p_l: Point) -> f64 {
-    let (x_k, y_k) = p_k;
-    let (x_l, y_l) = p_l;
-    let xlk = x_l - x_k;
-    let ylk = y_l - y_k;
-    f64::sqrt((xlk * xlk + ylk * ylk) as f64)
-}
-
 fn main() {
     let mut buffer: Vec<u32> = vec![WHITE; WINDOW_WIDTH * WINDOW_HEIGHT];
     let mut window = Window::new(
@@ -156,13 +118,18 @@ fn main() {
         let perp1 = perp_bisector(p_a, p_b);
         let perp2 = perp_bisector(p_b, p_c);
 
-        let centre = find_intersection(perp1, perp2);
-        let radius = distance_between_two_points(centre, p_a);
+        // Collinear points have no circle through them.
+        let centre = perp1
+            .zip(perp2)
+            .and_then(|(l1, l2)| find_intersection(l1, l2));
 
         image.plot_line_width(p_a, p_b, 2.5);
         image.plot_line_width(p_b, p_c, 2.5);
         image.plot_line_width(p_c, p_a, 2.5);
-        image.plot_circle(centre, radius as i64, 2.0);
+        if let Some(centre) = centre {
+            let radius = distance_between_two_points(centre, p_a);
+            image.plot_circle(centre, radius as i64, 2.0);
+        }
         image.draw(&mut buffer, BLACK, None, WINDOW_WIDTH);
 
         window
@@ -0,0 +1,77 @@
+use bitmappers_companion::*;
+use minifb::{Key, Window, WindowOptions};
+
+const METHODS: &str = "floyd-steinberg, atkinson, jarvis-judice-ninke, stucki, burkes, sierra, \
+                       two-row-sierra, sierra-lite, bayer, clustered-dot, diagonal-clustered-dot, \
+                       blue-noise, riemersma";
+
+// Usage: dither [METHOD] [OUTPUT_FILE], with Floyd-Steinberg by default.
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let method = args.next().unwrap_or_else(|| "floyd-steinberg".to_string());
+    let output_file = args.next();
+
+    const INPUT_FILE: &str = "./testimages/4.2.06.ppm";
+    let image = Image::from_netpbm(INPUT_FILE, 0, 0).unwrap();
+    let width = image.width;
+    let height = image.height;
+    let mut buffer: Vec<u32> = vec![WHITE; width * height];
+
+    // Ordered and Riemersma dithering ignore the scan order.
+    let options = DitherOptions {
+        serpentine: true,
+        linearize: true,
+        luma: Luma::Rec709,
+        ..DitherOptions::default()
+    };
+    let image = match method.as_str() {
+        "floyd-steinberg" => image.dither_with(&Kernel::floyd_steinberg(), &options),
+        "atkinson" => image.dither_with(&Kernel::atkinson(), &options),
+        "jarvis-judice-ninke" => image.dither_with(&Kernel::jarvis_judice_ninke(), &options),
+        "stucki" => image.dither_with(&Kernel::stucki(), &options),
+        "burkes" => image.dither_with(&Kernel::burkes(), &options),
+        "sierra" => image.dither_with(&Kernel::sierra(), &options),
+        "two-row-sierra" => image.dither_with(&Kernel::two_row_sierra(), &options),
+        "sierra-lite" => image.dither_with(&Kernel::sierra_lite(), &options),
+        "bayer" => image.dither_ordered(&ThresholdMap::bayer(8).unwrap(), &options),
+        "clustered-dot" => image.dither_ordered(&ThresholdMap::clustered_dot(8).unwrap(), &options),
+        "diagonal-clustered-dot" => {
+            image.dither_ordered(&ThresholdMap::diagonal_clustered_dot(8).unwrap(), &options)
+        }
+        "blue-noise" => image.dither_ordered(&ThresholdMap::blue_noise(32, 1).unwrap(), &options),
+        "riemersma" => image.dither_riemersma(&options),
+        _ => {
+            eprintln!("Unknown method `{}`, expected one of {}.", method, METHODS);
+            std::process::exit(1);
+        }
+    };
+    if let Some(output_file) = output_file {
+        image.save_pbm(&output_file, true).unwrap();
+    }
+
+    let mut window = Window::new(
+        &format!("Dithering ({}) - ESC to exit", method),
+        width,
+        height,
+        WindowOptions {
+            title: true,
+            //borderless: true,
+            resize: true,
+            //transparency: true,
+            ..WindowOptions::default()
+        },
+    )
+    .unwrap();
+
+    // Limit to max ~60 fps update rate
+    window.limit_update_rate(Some(std::time::Duration::from_micros(16600)));
+
+    image.draw_raw(&mut buffer, width);
+    while window.is_open() && !window.is_key_down(Key::Escape) && !window.is_key_down(Key::Q) {
+        window.update_with_buffer(&buffer, width, height).unwrap();
+
+        let millis = std::time::Duration::from_millis(100);
+
+        std::thread::sleep(millis);
+    }
+}
@@ -4,39 +4,6 @@ use minifb::{CursorStyle, Key, MouseButton, MouseMode, Window, WindowOptions};
 const WINDOW_WIDTH: usize = 300;
 const WINDOW_HEIGHT: usize = 300;
 
-fn find_equidistant(point_a: Point, point_b: Point) -> (i64, i64, i64) {
-    let (xa, ya) = point_a;
-    let (xb, yb) = point_b;
-    let midpoint = ((xa + xb) / 2, (ya + yb) / 2);
-
-    let al = ya - yb;
-    let bl = xb - xa;
-
-    // If we had subpixel accuracy, we could do:
-    //assert_eq!(al*midpoint.0+bl*midpoint.1, -cl);
-
-    let a = bl;
-    let b = -al;
-    let c = (al * midpoint.1) - (bl * midpoint.0);
-
-    (a, b, c)
-}
-
-fn plot_line(image: &mut Image, (a, b, c): (i64, i64, i64)) {
-    let x = if a != 0 { -c / a } else { 0 };
-    let mut prev_point = (x, 0);
-    for y in 0..(WINDOW_HEIGHT as i64) {
-        // ax+by+c =0 =>
-        // x=(-c-by)/a
-
-        let x = if a != 0 { -(c + b * y) / a } else { 0 };
-        let new_point = (x, y);
-        image.plot_line_width(prev_point, new_point, 1.0);
-        prev_point = new_point;
-        //image.plot(x, y);
-    }
-}
-
 fn main() {
     let mut buffer: Vec<u32> = vec![WHITE; WINDOW_WIDTH * WINDOW_HEIGHT];
     let mut window = Window::new(
@@ -125,7 +92,9 @@ fn main() {
         image.plot_circle(p_m, 3, 0.);
         image.plot_circle(p_n, 3, 0.);
 
-        plot_line(&mut image, find_equidistant(p_m, p_n));
+        if let Some(l) = perp_bisector(p_m, p_n) {
+            image.plot_line(l, 1.0);
+        }
         image.draw(&mut buffer, BLACK, None, WINDOW_WIDTH);
 
         window
@@ -31,8 +31,8 @@ fn floyd(image: &mut Image) {
 }
 
 fn main() {
-    const INPUT_FILE: &str = "./testimages/4.2.06.tiff";
-    let mut image = Image::magick_open(INPUT_FILE, 0, 0).unwrap();
+    const INPUT_FILE: &str = "./testimages/4.2.06.ppm";
+    let mut image = Image::from_netpbm(INPUT_FILE, 0, 0).unwrap();
     let width = image.width;
     let height = image.height;
     let mut buffer: Vec<u32> = vec![WHITE; width * height];
@@ -55,6 +55,9 @@ fn main() {
     window.limit_update_rate(Some(std::time::Duration::from_micros(16600)));
 
     floyd(&mut image);
+    if let Some(output_file) = std::env::args().nth(1) {
+        image.save_pbm(&output_file, true).unwrap();
+    }
     image.draw_raw(&mut buffer, width);
     while window.is_open() && !window.is_key_down(Key::Escape) && !window.is_key_down(Key::Q) {
         window.update_with_buffer(&buffer, width, height).unwrap();
@@ -44,6 +44,14 @@ fn main() {
     //unifont.bytes = bits_to_bytes(UNIFONT_BITS, UNIFONT_WIDTH);
     //let unifont = BitmapFont::new(unifont, (16, 16), 0, 0);
     image.write_str(&bizcat, "hello world!", (0, 0));
+    if let Some(font_path) = std::env::args().nth(1) {
+        let font = if font_path.ends_with(".bdf") {
+            BitmapFont::from_bdf(&font_path).unwrap()
+        } else {
+            BitmapFont::from_psf(&font_path).unwrap()
+        };
+        image.write_str(&font, "Unicode text: ÅßΩж", (0, 20));
+    }
 
     image.draw(&mut buffer, BLACK, None, WINDOW_WIDTH);
     while window.is_open() && !window.is_key_down(Key::Escape) && !window.is_key_down(Key::Q) {
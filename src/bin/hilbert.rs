@@ -3,6 +3,8 @@ use minifb::{Key, Window, WindowOptions};
 
 const WINDOW_WIDTH: usize = 200;
 const WINDOW_HEIGHT: usize = 200;
+// The recursive curve on the left, the library's traversal on the right.
+const BUFFER_WIDTH: usize = 2 * WINDOW_WIDTH;
 
 const HILBERT: &[&[usize]] = &[
     &[22, 10, 16, 38],
@@ -73,10 +75,10 @@ fn curve(img: &mut Image, k: usize, order: i64, mut x: i64, mut y: i64) -> (i64,
 }
 
 fn main() {
-    let mut buffer: Vec<u32> = vec![WHITE; WINDOW_WIDTH * WINDOW_HEIGHT];
+    let mut buffer: Vec<u32> = vec![WHITE; BUFFER_WIDTH * WINDOW_HEIGHT];
     let mut window = Window::new(
         "Test - ESC to exit",
-        WINDOW_WIDTH,
+        BUFFER_WIDTH,
         WINDOW_HEIGHT,
         WindowOptions {
             title: true,
@@ -94,11 +96,25 @@ fn main() {
     let mut image = Image::new(WINDOW_WIDTH, WINDOW_WIDTH, 0, 0);
     curve(&mut image, 0, 10, 0, WINDOW_WIDTH as i64);
 
+    // `Hilbert` works for any size, not only powers of two.
+    const STEP_SIZE: i64 = 5;
+    let mut traversal = Image::new(WINDOW_WIDTH, WINDOW_HEIGHT, WINDOW_WIDTH, 0);
+    let points = Hilbert::new(
+        WINDOW_WIDTH / STEP_SIZE as usize,
+        WINDOW_HEIGHT / STEP_SIZE as usize,
+    )
+    .map(|(x, y)| (x * STEP_SIZE + 2, y * STEP_SIZE + 2))
+    .collect::<Vec<_>>();
+    for pair in points.windows(2) {
+        traversal.plot_line_width(pair[0], pair[1], 0.);
+    }
+
     while window.is_open() && !window.is_key_down(Key::Escape) && !window.is_key_down(Key::Q) {
-        image.draw(&mut buffer, BLACK, None, WINDOW_WIDTH);
+        image.draw(&mut buffer, BLACK, None, BUFFER_WIDTH);
+        traversal.draw(&mut buffer, BLACK, None, BUFFER_WIDTH);
 
         window
-            .update_with_buffer(&buffer, WINDOW_WIDTH, WINDOW_HEIGHT)
+            .update_with_buffer(&buffer, BUFFER_WIDTH, WINDOW_HEIGHT)
             .unwrap();
 
         let millis = std::time::Duration::from_millis(100);
@@ -4,41 +4,6 @@ use minifb::{CursorStyle, Key, MouseButton, MouseMode, Window, WindowOptions};
 const WINDOW_WIDTH: usize = 300;
 const WINDOW_HEIGHT: usize = 300;
 
-fn find_intersection((a1, b1, c1): (i64, i64, i64), (a2, b2, c2): (i64, i64, i64)) -> Point {
-    let denom = a1 * b2 - a2 * b1;
-
-    if denom == 0 {
-        return (0, 0);
-    }
-
-    ((b1 * c2 - b2 * c1) / denom, (a2 * c1 - a1 * c2) / denom)
-}
-
-fn find_line(point_a: Point, point_b: Point) -> (i64, i64, i64) {
-    let (xa, ya) = point_a;
-    let (xb, yb) = point_b;
-    let a = ya - yb;
-    let b = xb - xa;
-    let c = xa * yb - xb * ya;
-
-    (a, b, c)
-}
-
-fn plot_line(image: &mut Image, (a, b, c): (i64, i64, i64)) {
-    let x = if a != 0 { -c / a } else { 0 };
-    let mut prev_point = (x, 0);
-    for y in 0..(WINDOW_HEIGHT as i64) {
-        // ax+by+c =0 =>
-        // x=(-c-by)/a
-
-        let x = if a != 0 { -(c + b * y) / a } else { 0 };
-        let new_point = (x, y);
-        image.plot_line_width(prev_point, new_point, 1.0);
-        prev_point = new_point;
-        //image.plot(x, y);
-    }
-}
-
 fn main() {
     let mut buffer: Vec<u32> = vec![WHITE; WINDOW_WIDTH * WINDOW_HEIGHT];
     let mut window = Window::new(
@@ -113,11 +78,13 @@ fn main() {
 
         let l1 = find_line(points[0], points[1]);
         let l2 = find_line(points[2], points[3]);
-        plot_line(&mut image, l1);
-        plot_line(&mut image, l2);
+        for l in [l1, l2].into_iter().flatten() {
+            image.plot_line(l, 1.0);
+        }
 
-        let p = find_intersection(l1, l2);
-        image.plot_circle(p, 5, 3.);
+        if let Some(p) = l1.zip(l2).and_then(|(l1, l2)| find_intersection(l1, l2)) {
+            image.plot_circle(p, 5, 3.);
+        }
 
         image.draw(&mut buffer, BLACK, None, WINDOW_WIDTH);
 
@@ -4,34 +4,6 @@ use minifb::{CursorStyle, Key, MouseButton, MouseMode, Window, WindowOptions};
 const WINDOW_WIDTH: usize = 600;
 const WINDOW_HEIGHT: usize = 600;
 
-fn find_mirror(point: Point, l: Line) -> Point {
-    let (x, y) = point;
-    let (a, b, c) = l;
-    let (a, b, c) = (a as f64, b as f64, c as f64);
-
-    let b2a = (b * b) / a;
-    let mx = (b2a * x as f64 - c - b * y as f64) / (a + b2a);
-    let my = (-a * mx - c) / b;
-    let (mx, my) = (mx as i64, my as i64);
-
-    (2 * mx - x, 2 * my - y)
-}
-
-fn plot_line(image: &mut Image, (a, b, c): (i64, i64, i64)) {
-    let x = if a != 0 { -c / a } else { 0 };
-    let mut prev_point = (x, 0);
-    for y in 0..(WINDOW_HEIGHT as i64) {
-        // ax+by+c =0 =>
-        // x=(-c-by)/a
-
-        let x = if a != 0 { -(c + b * y) / a } else { 0 };
-        let new_point = (x, y);
-        image.plot_line_width(prev_point, new_point, 1.0);
-        prev_point = new_point;
-        //image.plot(x, y);
-    }
-}
-
 fn main() {
     let mut buffer: Vec<u32> = vec![WHITE; WINDOW_WIDTH * WINDOW_HEIGHT];
     let mut window = Window::new(
@@ -99,9 +71,11 @@ fn main() {
         }
 
         image.plot_circle(p_m, 3, 0.);
-        image.plot_circle(find_mirror(p_m, l), 3, 0.);
+        if let Some(p) = find_mirror(p_m, l) {
+            image.plot_circle(p, 3, 0.);
+        }
 
-        plot_line(&mut image, l);
+        image.plot_line(l, 1.0);
         image.draw(&mut buffer, BLACK, None, WINDOW_WIDTH);
 
         window
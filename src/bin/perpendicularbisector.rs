@@ -4,40 +4,6 @@ use minifb::{CursorStyle, Key, MouseButton, MouseMode, Window, WindowOptions};
 const WINDOW_WIDTH: usize = 300;
 const WINDOW_HEIGHT: usize = 300;
 
-fn find_equidistant(point_a: Point, point_b: Point) -> (i64, i64, i64) {
-    let (xa, ya) = point_a;
-    let (xb, yb) = point_b;
-    let midpoint = ((xa + xb) / 2, (ya + yb) / 2);
-
-    let al = ya - yb;
-    let bl = xb - xa;
-
-    // If we had subpixel accuracy, we could do:
-    //assert_eq!(al*midpoint.0+bl*midpoint.1, -cl);
-
-    let a = bl;
-    let b = -al;
-    let c = (al * midpoint.1) - (bl * midpoint.0);
-
-    (a, b, c)
-}
-
-fn plot_line(image: &mut Image, (a, b, c): (i64, i64, i64)) {
-    let x = if a != 0 { -c / a } else { 0 };
-    let mut prev_point = (x, 0);
-    for y in 0..(WINDOW_HEIGHT as i64) {
-        // ax+by+c =0 =>
-        // x=(-c-by)/a
-
-        let x = if a != 0 { -(c + b * y) / a } else { 0 };
-        let new_point = (x, y);
-        //eprintln!("{prev_point:?} {new_point:?}");
-        image.plot_line_width_color(prev_point, new_point, 2.0, None);
-        prev_point = new_point;
-        image.plot(x, y);
-    }
-}
-
 fn main() {
     let mut buffer: Vec<u32> = vec![WHITE; WINDOW_WIDTH * WINDOW_HEIGHT];
     let mut window = Window::new(
@@ -126,37 +92,10 @@ fn main() {
         image.plot_circle(p_m, 3, 0.);
         image.plot_circle(p_n, 3, 0.);
 
-        if p_n.0 == p_n.1 {
-            //std::mem::swap(&mut p_n, &mut p_m);
-        }
-
-        let m_a = if p_n.0 != p_n.1 {
-            (p_m.1 - p_n.1) as f64
-        } else {
-            -1.0
-        };
-        let m_b = (p_n.0 - p_m.0) as f64;
-
-        let (x_m, y_m) = ((p_n.0 + p_m.0) as f64 / 2.0, (p_n.1 + p_m.1) as f64 / 2.0);
-        // slope form y=mx+b
-        // m_og = (y_m - y_n / x_m - x_n)
-        // m_og * m = -1 => m = (x_n - x_m) / (y_m - y_n) = m_b / m_a
-        //
-        // y = mx+b => y_m = m*x_m + b => b = y_m - m * x_m
-        //
-        // slope form y=mx+b -> implicit form αx+βy=γ
-        // y = m*x + y_m - m* x_m
-
         image.plot_line_width(p_m, p_n, 1.5);
-        eprintln!("line {:?} {:?} {:?}", m_a, -m_b, (y_m * m_a) / x_m);
-        plot_line(
-            &mut image,
-            (
-                m_b as i64,
-                -m_a as i64,
-                (((y_m * m_a) - (m_b * x_m)) as i64),
-            ),
-        );
+        if let Some(l) = perp_bisector(p_m, p_n) {
+            image.plot_line(l, 2.0);
+        }
         image.draw(&mut buffer, BLACK, None, WINDOW_WIDTH);
 
         window
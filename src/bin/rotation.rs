@@ -23,7 +23,7 @@ pub fn bits_to_bytes(bits: &[u8], width: usize) -> Vec<u32> {
 
 include!("../dmr.xbm.rs");
 
-const WINDOW_WIDTH: usize = 100;
+const WINDOW_WIDTH: usize = 200;
 const WINDOW_HEIGHT: usize = 100;
 
 fn main() {
@@ -66,8 +66,17 @@ fn main() {
     }
     image.draw_outline();
 
+    // The same rotation by shearing, which leaves no holes, on the right.
+    let mut rotated = Image::new(DMR_WIDTH, DMR_HEIGHT, 0, 0);
+    rotated.bytes = dmr;
+    let mut rotated = rotated.rotate(angle);
+    rotated.x_offset = WINDOW_WIDTH / 2 + (WINDOW_WIDTH / 2).saturating_sub(rotated.width) / 2;
+    rotated.y_offset = WINDOW_HEIGHT.saturating_sub(rotated.height) / 2;
+    rotated.draw_outline();
+
     while window.is_open() && !window.is_key_down(Key::Escape) && !window.is_key_down(Key::Q) {
         image.draw(&mut buffer, BLACK, None, WINDOW_WIDTH);
+        rotated.draw(&mut buffer, BLACK, None, WINDOW_WIDTH);
 
         window
             .update_with_buffer(&buffer, WINDOW_WIDTH, WINDOW_HEIGHT)
@@ -4,38 +4,6 @@ use minifb::{CursorStyle, Key, MouseButton, MouseMode, Window, WindowOptions};
 const WINDOW_WIDTH: usize = 300;
 const WINDOW_HEIGHT: usize = 300;
 
-type Line = (i64, i64, i64);
-pub fn distance_line_to_point((x, y): Point, (a, b, c): Line) -> f64 {
-    let d = f64::sqrt((a * a + b * b) as f64);
-    if d == 0.0 {
-        0.
-    } else {
-        (a * x + b * y + c) as f64 / d
-    }
-}
-
-fn find_line(point_a: Point, point_b: Point) -> (i64, i64, i64) {
-    let (xa, ya) = point_a;
-    let (xb, yb) = point_b;
-    let a = yb - ya;
-    let b = xa - xb;
-    let c = xb * ya - xa * yb;
-
-    (a, b, c)
-}
-
-fn point_perpendicular((a, b, c): Line, p: Point) -> Point {
-    let d = (a * a + b * b) as f64;
-    if d == 0. {
-        return (0, 0);
-    }
-    let cp = a * p.1 - b * p.0;
-    (
-        ((-a * c - b * cp) as f64 / d) as i64,
-        ((a * cp - b * c) as f64 / d) as i64,
-    )
-}
-
 fn cross2(v1: Point, v2: Point) -> f64 {
     v1.0 as f64 * v2.1 as f64 - v2.0 as f64 * v1.1 as f64
 }
@@ -73,8 +41,11 @@ fn draw_arc(image: &mut Image, p: Point, r: f64, startangle: f64, angle: f64) {
 fn round_corner(image: &mut Image, (p1, mut p2): (Point, Point), (mut p3, p4): (Point, Point)) {
     const R: f64 = 20.;
 
-    let l1 = find_line(p1, p2);
-    let l2 = find_line(p3, p4);
+    // Parallel lines have no corner to round.
+    let (l1, l2) = match (find_line(p1, p2), find_line(p3, p4)) {
+        (Some(l1), Some(l2)) if find_intersection(l1, l2).is_some() => (l1, l2),
+        _ => return,
+    };
 
     let (a1, b1, _c1) = l1;
     let (a2, b2, _c2) = l2;
@@ -82,9 +53,9 @@ fn round_corner(image: &mut Image, (p1, mut p2): (Point, Point), (mut p3, p4): (
     let m1 = ((p1.0 + p2.0) / 2, (p1.1 + p2.1) / 2);
     let m2 = ((p3.0 + p4.0) / 2, (p3.1 + p4.1) / 2);
 
-    let d1 = distance_line_to_point(m2, l1);
+    let d1 = distance_line_to_point(m2, l1).unwrap_or(0.);
 
-    let d2 = distance_line_to_point(m1, l2);
+    let d2 = distance_line_to_point(m1, l2).unwrap_or(0.);
 
     let mut rr = R;
     if d1 <= 0. {
@@ -104,12 +75,13 @@ fn round_corner(image: &mut Image, (p1, mut p2): (Point, Point), (mut p3, p4): (
         ((c1p * (a2 as f64) - c2p * (a1 as f64)) / d) as i64,
     );
 
-    let (xa, ya) = point_perpendicular(l1, p_c);
-
-    image.plot_circle(std::dbg!(point_perpendicular(l1, p_c)), 3, 1.);
-    image.plot_circle(std::dbg!(point_perpendicular(l2, p_c)), 3, 1.);
+    let ((xa, ya), (xb, yb)) = match (point_perpendicular(l1, p_c), point_perpendicular(l2, p_c)) {
+        (Some(a), Some(b)) => (a, b),
+        _ => return,
+    };
 
-    let (xb, yb) = point_perpendicular(l2, p_c);
+    image.plot_circle((xa, ya), 3, 1.);
+    image.plot_circle((xb, yb), 3, 1.);
 
     p2 = (xa, ya);
 
@@ -121,7 +93,7 @@ fn round_corner(image: &mut Image, (p1, mut p2): (Point, Point), (mut p3, p4): (
     let pa = f64::atan2(v1.1 as f64, v1.0 as f64);
 
     let mut aa = dot2(v1, v2) * 57.2958;
-    if std::dbg!(cross2(v1, v2)) < 0. {
+    if cross2(v1, v2) < 0. {
         aa = -aa;
     }
 
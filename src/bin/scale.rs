@@ -1,14 +1,6 @@
 use bitmappers_companion::*;
 use minifb::{Key, Window, WindowOptions};
 
-pub fn distance_between_two_points(p_k: Point, p_l: Point) -> f64 {
-    let (x_k, y_k) = p_k;
-    let (x_l, y_l) = p_l;
-    let xlk = x_l - x_k;
-    let ylk = y_l - y_k;
-    f64::sqrt((xlk * xlk + ylk * ylk) as f64)
-}
-
 include!("../dmr.xbm.rs");
 const WINDOW_WIDTH: usize = 400;
 const WINDOW_HEIGHT: usize = 400;
@@ -23,7 +23,7 @@ pub fn bits_to_bytes(bits: &[u8], width: usize) -> Vec<u32> {
 
 include!("../dmr.xbm.rs");
 
-const WINDOW_WIDTH: usize = 150;
+const WINDOW_WIDTH: usize = 300;
 const WINDOW_HEIGHT: usize = 150;
 
 fn shear_x((x_p, y_p): (i64, i64), l: f64) -> (i64, i64) {
@@ -70,8 +70,15 @@ fn main() {
     }
     sheared.draw_outline();
 
+    // The same shear as an affine transform, on the right.
+    let mut warped = image.warp(&Affine::shear(l, 0.), Sampling::Nearest);
+    warped.x_offset = WINDOW_WIDTH / 2 + 25;
+    warped.y_offset = 25;
+    warped.draw_outline();
+
     while window.is_open() && !window.is_key_down(Key::Escape) && !window.is_key_down(Key::Q) {
         sheared.draw(&mut buffer, BLACK, None, WINDOW_WIDTH);
+        warped.draw(&mut buffer, BLACK, None, WINDOW_WIDTH);
 
         window
             .update_with_buffer(&buffer, WINDOW_WIDTH, WINDOW_HEIGHT)
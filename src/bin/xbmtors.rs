@@ -1,4 +1,4 @@
-use regex::Regex;
+use bitmappers_companion::Xbm;
 use std::fs::File;
 use std::io::prelude::*;
 
@@ -18,21 +18,24 @@ fn main() {
         panic!("couldn't read {}: {}", args[0], err);
     }
 
-    let re = Regex::new(
-        r"(?imx)
-  ^\s*\x23\s*define\s+(?P<i>.+?)_width\s+(?P<w>\d\d*)$
-  \s*
-  ^\s*\x23\s*define\s+.+?_height\s+(?P<h>\d\d*)$
-  \s*
-  ^\s*static(\s+unsigned){0,1}\s+char\s+.+?_bits..\s*=\s*\{(?P<b>[^}]+)\};
-",
-    )
-    .unwrap();
-
-    let caps = re
-        .captures(&s)
-        .expect("Could not convert file, regex doesn't match :(");
-    let ident = caps.name("i").unwrap().as_str().to_uppercase();
-    let out = re.replace_all(&s, format!("const {i}_WIDTH: usize = $w;\nconst {i}_HEIGHT: usize = $h;\nconst {i}_BITS: &[u8] = &[$b];", i = &ident));
-    println!("{}", out.trim());
+    let xbm = match Xbm::parse(&s) {
+        Err(err) => panic!("couldn't convert {}: {}", args[0], err),
+        Ok(xbm) => xbm,
+    };
+    let ident = xbm.name.to_uppercase();
+    println!("const {}_WIDTH: usize = {};", ident, xbm.width);
+    println!("const {}_HEIGHT: usize = {};", ident, xbm.height);
+    if let Some((x_hot, y_hot)) = xbm.hotspot {
+        println!("const {}_X_HOT: usize = {};", ident, x_hot);
+        println!("const {}_Y_HOT: usize = {};", ident, y_hot);
+    }
+    println!("const {}_BITS: &[u8] = &[", ident);
+    for row in xbm.bits.chunks(12) {
+        let row = row
+            .iter()
+            .map(|b| format!("0x{:02x},", b))
+            .collect::<Vec<String>>();
+        println!("   {}", row.join(" "));
+    }
+    println!("];");
 }
@@ -0,0 +1,313 @@
+use crate::{Image, BLACK, WHITE};
+use std::collections::VecDeque;
+
+const WORD_BITS: usize = u64::BITS as usize;
+
+/// A 1-bit-per-pixel image.
+///
+/// Every row starts on a word boundary and is `stride` words long. Bit `x % 64` of word `x / 64`
+/// is the pixel at column `x`, which is the same LSB-first order XBM files use, and a set bit is
+/// a `BLACK` pixel. Padding bits past `width` are always kept clear.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Bitmap {
+    pub words: Vec<u64>,
+    pub width: usize,
+    pub height: usize,
+    pub stride: usize,
+    pub x_offset: usize,
+    pub y_offset: usize,
+}
+
+impl Bitmap {
+    pub fn new(width: usize, height: usize, x_offset: usize, y_offset: usize) -> Self {
+        let stride = width.div_ceil(WORD_BITS);
+        Bitmap {
+            words: vec![0; stride * height],
+            width,
+            height,
+            stride,
+            x_offset,
+            y_offset,
+        }
+    }
+
+    /// Packs XBM-style bits, the same input `bits_to_bytes` takes. `None` if `bits` isn't a whole
+    /// number of rows of `width.div_ceil(8)` bytes.
+    pub fn from_bits(bits: &[u8], width: usize, x_offset: usize, y_offset: usize) -> Option<Self> {
+        let bytes_per_row = width.div_ceil(8);
+        if bytes_per_row == 0 {
+            return bits
+                .is_empty()
+                .then(|| Bitmap::new(0, 0, x_offset, y_offset));
+        }
+        if !bits.len().is_multiple_of(bytes_per_row) {
+            return None;
+        }
+        let mut ret = Bitmap::new(width, bits.len() / bytes_per_row, x_offset, y_offset);
+        for (y, row) in bits.chunks(bytes_per_row).enumerate() {
+            for (i, byte) in row.iter().enumerate() {
+                ret.words[y * ret.stride + i / 8] |= (*byte as u64) << ((i % 8) * 8);
+            }
+            ret.mask_row(y);
+        }
+        Some(ret)
+    }
+
+    /// Inverse of `from_bits`: rows padded to whole bytes, LSB first.
+    pub fn to_bits(&self) -> Vec<u8> {
+        let bytes_per_row = self.width.div_ceil(8);
+        let mut ret = Vec::with_capacity(bytes_per_row * self.height);
+        for y in 0..self.height {
+            let row = &self.words[y * self.stride..(y + 1) * self.stride];
+            for i in 0..bytes_per_row {
+                ret.push((row[i / 8] >> ((i % 8) * 8)) as u8);
+            }
+        }
+        ret
+    }
+
+    fn mask_row(&mut self, y: usize) {
+        let rem = self.width % WORD_BITS;
+        if rem != 0 {
+            self.words[y * self.stride + self.stride - 1] &= (1 << rem) - 1;
+        }
+    }
+
+    #[inline]
+    fn in_bounds(&self, x: i64, y: i64) -> bool {
+        x >= 0 && y >= 0 && y < (self.height as i64) && x < (self.width as i64)
+    }
+
+    #[inline]
+    fn index(&self, x: usize, y: usize) -> (usize, u64) {
+        (y * self.stride + x / WORD_BITS, 1 << (x % WORD_BITS))
+    }
+
+    pub fn is_set(&self, x: i64, y: i64) -> bool {
+        if !self.in_bounds(x, y) {
+            return false;
+        }
+        let (i, mask) = self.index(x as _, y as _);
+        self.words[i] & mask != 0
+    }
+
+    pub fn set(&mut self, x: i64, y: i64, value: bool) {
+        if !self.in_bounds(x, y) {
+            return;
+        }
+        let (i, mask) = self.index(x as _, y as _);
+        if value {
+            self.words[i] |= mask;
+        } else {
+            self.words[i] &= !mask;
+        }
+    }
+
+    pub fn plot(&mut self, x: i64, y: i64) {
+        self.set(x, y, true);
+    }
+
+    /// Any color other than `BLACK` clears the pixel.
+    pub fn plot_color(&mut self, x: i64, y: i64, color: Option<u32>) {
+        self.set(x, y, color.unwrap_or(BLACK) == BLACK);
+    }
+
+    pub fn get(&self, x: i64, y: i64) -> Option<u32> {
+        if !self.in_bounds(x, y) {
+            return None;
+        }
+        Some(if self.is_set(x, y) { BLACK } else { WHITE })
+    }
+
+    pub fn clear(&mut self) {
+        for w in self.words.iter_mut() {
+            *w = 0;
+        }
+    }
+
+    pub fn count_ones(&self) -> usize {
+        self.words.iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    pub fn draw(&self, buffer: &mut [u32], fg: u32, bg: Option<u32>, window_width: usize) {
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let pos = (self.y_offset + y) * window_width + self.x_offset + x;
+                if self.is_set(x as i64, y as i64) {
+                    buffer[pos] = fg;
+                } else if let Some(bg) = bg {
+                    buffer[pos] = bg;
+                }
+            }
+        }
+    }
+
+    /// Reads `len <= 64` bits of row `y` starting at column `x`.
+    fn read_bits(&self, x: usize, y: usize, len: usize) -> u64 {
+        let row = y * self.stride;
+        let (w, shift) = (x / WORD_BITS, x % WORD_BITS);
+        let mut ret = self.words[row + w] >> shift;
+        if shift != 0 && w + 1 < self.stride {
+            ret |= self.words[row + w + 1] << (WORD_BITS - shift);
+        }
+        if len < WORD_BITS {
+            ret &= (1 << len) - 1;
+        }
+        ret
+    }
+
+    /// ORs `bits` into row `y` starting at column `x`. The caller keeps `bits` inside the row.
+    fn or_bits(&mut self, x: usize, y: usize, bits: u64) {
+        let row = y * self.stride;
+        let (w, shift) = (x / WORD_BITS, x % WORD_BITS);
+        self.words[row + w] |= bits << shift;
+        if shift != 0 && w + 1 < self.stride {
+            self.words[row + w + 1] |= bits >> (WORD_BITS - shift);
+        }
+    }
+
+    /// Same semantics as `Image::copy`: `BLACK` source pixels are drawn over the destination.
+    pub fn copy(
+        &mut self,
+        source: &Bitmap,
+        (x_offset, y_offset): (usize, usize),
+        (sx_offset, sy_offset): (usize, usize),
+        width: usize,
+        height: usize,
+    ) {
+        let width = width
+            .min(self.width.saturating_sub(x_offset))
+            .min(source.width.saturating_sub(sx_offset));
+        let height = height
+            .min(self.height.saturating_sub(y_offset))
+            .min(source.height.saturating_sub(sy_offset));
+        for row in 0..height {
+            let mut col = 0;
+            while col < width {
+                let len = (width - col).min(WORD_BITS);
+                let bits = source.read_bits(sx_offset + col, sy_offset + row, len);
+                self.or_bits(x_offset + col, y_offset + row, bits);
+                col += len;
+            }
+        }
+    }
+
+    /// Fills the 4-connected area of clear pixels containing `(x, y)`.
+    pub fn flood_fill(&mut self, x: i64, y: i64) {
+        if self.get(x, y) != Some(WHITE) {
+            return;
+        }
+
+        let w = self.width as i64;
+        let h = self.height as i64;
+
+        let mut s = VecDeque::new();
+        s.push_back((x, y));
+        while let Some((x, y)) = s.pop_back() {
+            if self.is_set(x, y) {
+                continue;
+            }
+            let mut x1 = x;
+            while x1 > 0 && !self.is_set(x1 - 1, y) {
+                x1 -= 1;
+            }
+            let mut span_above = false;
+            let mut span_below = false;
+            while x1 < w && !self.is_set(x1, y) {
+                self.plot(x1, y);
+                if y > 0 {
+                    let clear = !self.is_set(x1, y - 1);
+                    if !span_above && clear {
+                        s.push_back((x1, y - 1));
+                    }
+                    span_above = clear;
+                }
+                if y < h - 1 {
+                    let clear = !self.is_set(x1, y + 1);
+                    if !span_below && clear {
+                        s.push_back((x1, y + 1));
+                    }
+                    span_below = clear;
+                }
+                x1 += 1;
+            }
+        }
+    }
+
+    pub fn resize(
+        &self,
+        scaled_width: usize,
+        scaled_height: usize,
+        x_offset: usize,
+        y_offset: usize,
+    ) -> Bitmap {
+        let mut scaled = Bitmap::new(scaled_width, scaled_height, x_offset, y_offset);
+        let og_height = self.height as i64;
+        let og_width = self.width as i64;
+        for dy in 0..(scaled_height as i64) {
+            let sy = (dy * og_height) / scaled_height as i64;
+            for dx in 0..(scaled_width as i64) {
+                let sx = (dx * og_width) / scaled_width as i64;
+                if self.is_set(sx, sy) {
+                    scaled.plot(dx, dy);
+                }
+            }
+        }
+        scaled
+    }
+}
+
+/// Every `BLACK` pixel becomes a set bit, everything else is cleared.
+impl From<&Image> for Bitmap {
+    fn from(image: &Image) -> Self {
+        let mut ret = Bitmap::new(image.width, image.height, image.x_offset, image.y_offset);
+        for y in 0..image.height {
+            for (x, p) in image.bytes[y * image.width..(y + 1) * image.width]
+                .iter()
+                .enumerate()
+            {
+                if *p == BLACK {
+                    let (i, mask) = ret.index(x, y);
+                    ret.words[i] |= mask;
+                }
+            }
+        }
+        ret
+    }
+}
+
+impl From<&Bitmap> for Image {
+    fn from(bitmap: &Bitmap) -> Self {
+        let mut ret = Image::new(
+            bitmap.width,
+            bitmap.height,
+            bitmap.x_offset,
+            bitmap.y_offset,
+        );
+        for y in 0..bitmap.height {
+            for x in 0..bitmap.width {
+                if bitmap.is_set(x as i64, y as i64) {
+                    ret.bytes[y * bitmap.width + x] = BLACK;
+                }
+            }
+        }
+        ret
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_bits() {
+        let bits = [0xff, 0x1f, 0x01, 0x00];
+        let bitmap = Bitmap::from_bits(&bits, 13, 0, 0).unwrap();
+        assert_eq!((bitmap.width, bitmap.height), (13, 2));
+        assert_eq!(bitmap.to_bits(), bits);
+        assert_eq!(Bitmap::from(&Image::from(&bitmap)), bitmap);
+        assert!(Bitmap::from_bits(&bits[..3], 13, 0, 0).is_none());
+        assert!(Bitmap::from_bits(&bits, 0, 0, 0).is_none());
+        assert!(Bitmap::from_bits(&[], 0, 0, 0).is_some());
+    }
+}
@@ -0,0 +1,351 @@
+use crate::geometry::convex_hull;
+use crate::rng::XorShift;
+use crate::vector::{self, Vector};
+use crate::{Image, Point, BLACK};
+
+/// An axis-aligned rectangle of whole pixels.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Rect {
+    pub x: i64,
+    pub y: i64,
+    pub width: usize,
+    pub height: usize,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Circle {
+    pub center: vector::Point<f64>,
+    pub radius: f64,
+}
+
+/// A rectangle rotated so that its width runs along the unit vector `axis`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct OrientedRect {
+    pub center: vector::Point<f64>,
+    pub axis: Vector<f64>,
+    pub width: f64,
+    pub height: f64,
+}
+
+impl Rect {
+    pub fn contains(&self, (x, y): Point) -> bool {
+        x >= self.x
+            && y >= self.y
+            && x < self.x + self.width as i64
+            && y < self.y + self.height as i64
+    }
+
+    pub fn intersects(&self, other: &Rect) -> bool {
+        self.x < other.x + other.width as i64
+            && other.x < self.x + self.width as i64
+            && self.y < other.y + other.height as i64
+            && other.y < self.y + self.height as i64
+    }
+
+    /// The pixels in both rectangles, `None` if they don't overlap.
+    pub fn intersection(&self, other: &Rect) -> Option<Rect> {
+        if !self.intersects(other) {
+            return None;
+        }
+        let (x, y) = (self.x.max(other.x), self.y.max(other.y));
+        let right = (self.x + self.width as i64).min(other.x + other.width as i64);
+        let bottom = (self.y + self.height as i64).min(other.y + other.height as i64);
+        Some(Rect {
+            x,
+            y,
+            width: (right - x) as usize,
+            height: (bottom - y) as usize,
+        })
+    }
+}
+
+impl Circle {
+    pub fn contains(&self, p: vector::Point<f64>) -> bool {
+        // Tolerates rounding errors of points on the circle.
+        self.center.distance(p) <= self.radius * (1. + 1e-9) + 1e-9
+    }
+
+    fn diameter(p: vector::Point<f64>, q: vector::Point<f64>) -> Self {
+        Self {
+            center: p.midpoint(q),
+            radius: p.distance(q) / 2.,
+        }
+    }
+
+    /// The smallest circle with all three points on or inside it.
+    fn through(p: vector::Point<f64>, q: vector::Point<f64>, r: vector::Point<f64>) -> Self {
+        match vector::circle_through(p, q, r) {
+            Some((center, radius)) => Self { center, radius },
+            // Collinear points are enclosed by the circle over the two farthest apart.
+            None => [
+                Self::diameter(p, q),
+                Self::diameter(q, r),
+                Self::diameter(p, r),
+            ]
+            .into_iter()
+            .max_by(|a, b| a.radius.total_cmp(&b.radius))
+            .unwrap(),
+        }
+    }
+}
+
+impl OrientedRect {
+    pub fn area(&self) -> f64 {
+        self.width * self.height
+    }
+
+    pub fn corners(&self) -> [vector::Point<f64>; 4] {
+        let u = self.axis * (self.width / 2.);
+        let v = self.axis.perpendicular() * (self.height / 2.);
+        [
+            self.center - u - v,
+            self.center + u - v,
+            self.center + u + v,
+            self.center - u + v,
+        ]
+    }
+}
+
+/// Welzl's minimal enclosing circle, in its iterative form. The points are shuffled first,
+/// which makes it run in expected linear time.
+pub fn min_enclosing_circle(points: &[Point]) -> Option<Circle> {
+    let mut points = points
+        .iter()
+        .map(|p| vector::Point::from(*p).to_f64())
+        .collect::<Vec<_>>();
+    XorShift::new(0).shuffle(&mut points);
+
+    let mut circle = Circle {
+        center: *points.first()?,
+        radius: 0.,
+    };
+    for i in 1..points.len() {
+        if circle.contains(points[i]) {
+            continue;
+        }
+        // points[i] is on the boundary of the circle enclosing points[..=i].
+        circle = Circle {
+            center: points[i],
+            radius: 0.,
+        };
+        for j in 0..i {
+            if circle.contains(points[j]) {
+                continue;
+            }
+            circle = Circle::diameter(points[i], points[j]);
+            for k in 0..j {
+                if !circle.contains(points[k]) {
+                    circle = Circle::through(points[i], points[j], points[k]);
+                }
+            }
+        }
+    }
+    Some(circle)
+}
+
+/// The minimum-area rectangle enclosing `points`. One of its sides lies on an edge of their
+/// convex hull, so only the hull's edge directions are tried.
+pub fn min_area_rect(points: &[Point]) -> Option<OrientedRect> {
+    let hull = convex_hull(points)
+        .into_iter()
+        .map(|p| vector::Point::from(p).to_f64())
+        .collect::<Vec<_>>();
+    let first = *hull.first()?;
+    if hull.len() == 1 {
+        return Some(OrientedRect {
+            center: first,
+            axis: Vector::new(1., 0.),
+            width: 0.,
+            height: 0.,
+        });
+    }
+    let mut best: Option<OrientedRect> = None;
+    for (i, p) in hull.iter().enumerate() {
+        let axis = match (hull[(i + 1) % hull.len()] - *p).normalize() {
+            Some(axis) => axis,
+            None => continue,
+        };
+        let normal = axis.perpendicular();
+        let (mut u_min, mut u_max, mut v_min, mut v_max) = (f64::MAX, f64::MIN, f64::MAX, f64::MIN);
+        for q in &hull {
+            let d = *q - first;
+            let (u, v) = (d.dot(axis), d.dot(normal));
+            u_min = u_min.min(u);
+            u_max = u_max.max(u);
+            v_min = v_min.min(v);
+            v_max = v_max.max(v);
+        }
+        let rect = OrientedRect {
+            center: first + axis * ((u_min + u_max) / 2.) + normal * ((v_min + v_max) / 2.),
+            axis,
+            width: u_max - u_min,
+            height: v_max - v_min,
+        };
+        if best.is_none_or(|b| rect.area() < b.area()) {
+            best = Some(rect);
+        }
+    }
+    best
+}
+
+impl Image {
+    /// Coordinates of every `BLACK` pixel, row by row.
+    pub fn black_pixels(&self) -> Vec<Point> {
+        let mut ret = vec![];
+        for (i, p) in self.bytes.iter().enumerate() {
+            if *p == BLACK {
+                ret.push(((i % self.width) as i64, (i / self.width) as i64));
+            }
+        }
+        ret
+    }
+
+    /// The smallest rectangle containing every `BLACK` pixel, `None` if there are none.
+    pub fn bounding_box(&self) -> Option<Rect> {
+        let pixels = self.black_pixels();
+        let (x0, x1) = pixels
+            .iter()
+            .fold((i64::MAX, i64::MIN), |(a, b), p| (a.min(p.0), b.max(p.0)));
+        let (y0, y1) = (pixels.first()?.1, pixels.last()?.1);
+        Some(Rect {
+            x: x0,
+            y: y0,
+            width: (x1 - x0 + 1) as usize,
+            height: (y1 - y0 + 1) as usize,
+        })
+    }
+
+    /// The minimal circle enclosing the centers of every `BLACK` pixel.
+    pub fn min_enclosing_circle(&self) -> Option<Circle> {
+        min_enclosing_circle(&self.black_pixels())
+    }
+
+    /// The minimum-area rotated rectangle enclosing the centers of every `BLACK` pixel.
+    pub fn oriented_bounding_box(&self) -> Option<OrientedRect> {
+        min_area_rect(&self.black_pixels())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Pseudo-random point sets in `0..100`, from a linear congruential generator.
+    fn point_sets() -> Vec<Vec<Point>> {
+        let mut state = 1_u64;
+        let mut next = move || {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            ((state >> 33) % 100) as i64
+        };
+        (1..40)
+            .map(|n| (0..n % 13 + 1).map(|_| (next(), next())).collect())
+            .collect()
+    }
+
+    /// The smallest of the circles over two or through three of the points that encloses them
+    /// all.
+    fn brute_force_circle(points: &[Point]) -> f64 {
+        let points = points
+            .iter()
+            .map(|p| vector::Point::from(*p).to_f64())
+            .collect::<Vec<_>>();
+        let mut candidates = vec![Circle {
+            center: points[0],
+            radius: 0.,
+        }];
+        for (i, p) in points.iter().enumerate() {
+            for (j, q) in points.iter().enumerate().skip(i + 1) {
+                candidates.push(Circle::diameter(*p, *q));
+                for r in &points[j + 1..] {
+                    candidates.push(Circle::through(*p, *q, *r));
+                }
+            }
+        }
+        candidates
+            .into_iter()
+            .filter(|c| points.iter().all(|p| c.contains(*p)))
+            .map(|c| c.radius)
+            .fold(f64::MAX, f64::min)
+    }
+
+    #[test]
+    fn welzl() {
+        assert!(min_enclosing_circle(&[]).is_none());
+        assert_eq!(min_enclosing_circle(&[(3, 4)]).unwrap().radius, 0.);
+        let collinear = min_enclosing_circle(&[(0, 0), (2, 2), (6, 6), (3, 3)]).unwrap();
+        assert_eq!(collinear.center, vector::Point::new(3., 3.));
+        for points in point_sets() {
+            let circle = min_enclosing_circle(&points).unwrap();
+            for p in &points {
+                assert!(circle.contains(vector::Point::from(*p).to_f64()));
+            }
+            assert!((circle.radius - brute_force_circle(&points)).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn rects() {
+        let a = Rect {
+            x: 0,
+            y: 0,
+            width: 10,
+            height: 5,
+        };
+        let b = Rect {
+            x: 8,
+            y: -2,
+            width: 4,
+            height: 4,
+        };
+        assert_eq!(
+            a.intersection(&b),
+            Some(Rect {
+                x: 8,
+                y: 0,
+                width: 2,
+                height: 2,
+            })
+        );
+        assert!(a.contains((9, 4)) && !a.contains((10, 4)));
+        assert_eq!(a.intersection(&Rect { x: 10, ..b }), None);
+
+        let mut image = Image::new(20, 20, 0, 0);
+        for p in [(3, 7), (12, 4), (5, 15)] {
+            image.plot(p.0, p.1);
+        }
+        assert_eq!(
+            image.bounding_box(),
+            Some(Rect {
+                x: 3,
+                y: 4,
+                width: 10,
+                height: 12,
+            })
+        );
+        assert_eq!(Image::new(4, 4, 0, 0).bounding_box(), None);
+    }
+
+    #[test]
+    fn oriented_rect() {
+        // A diamond fits a rotated square exactly, with half the area of its bounding box.
+        let diamond = [(10, 0), (20, 10), (10, 20), (0, 10), (10, 10)];
+        let rect = min_area_rect(&diamond).unwrap();
+        assert!((rect.area() - 200.).abs() < 1e-9);
+        for points in point_sets() {
+            let rect = min_area_rect(&points).unwrap();
+            let (xs, ys) = (points.iter().map(|p| p.0), points.iter().map(|p| p.1));
+            let bbox_area = ((xs.clone().max().unwrap() - xs.min().unwrap())
+                * (ys.clone().max().unwrap() - ys.min().unwrap()))
+                as f64;
+            assert!(rect.area() <= bbox_area + 1e-9);
+            let normal = rect.axis.perpendicular();
+            for p in &points {
+                let d = vector::Point::from(*p).to_f64() - rect.center;
+                assert!(d.dot(rect.axis).abs() <= rect.width / 2. + 1e-9);
+                assert!(d.dot(normal).abs() <= rect.height / 2. + 1e-9);
+            }
+        }
+    }
+}
@@ -0,0 +1,336 @@
+//! Clipping of segments and polygons to a window before they're rasterized, so that shapes
+//! mostly outside the image don't cost a step per off-screen pixel.
+//!
+//! A `Rect` window covers the whole area of its pixels, i.e. `x - 0.5..=x + width - 0.5`, since
+//! pixel centers are at integer coordinates.
+
+use std::ops::RangeInclusive;
+
+use crate::vector;
+use crate::{Image, Point, Rect};
+
+type Vertex = (f64, f64);
+
+/// The continuous bounds `(x_min, y_min, x_max, y_max)` of `rect`, `None` if it's empty.
+fn window(rect: &Rect) -> Option<(f64, f64, f64, f64)> {
+    if rect.width == 0 || rect.height == 0 {
+        return None;
+    }
+    let (x, y) = (rect.x as f64 - 0.5, rect.y as f64 - 0.5);
+    Some((x, y, x + rect.width as f64, y + rect.height as f64))
+}
+
+const LEFT: u8 = 1;
+const RIGHT: u8 = 2;
+const TOP: u8 = 4;
+const BOTTOM: u8 = 8;
+
+fn outcode((x, y): Vertex, (x_min, y_min, x_max, y_max): (f64, f64, f64, f64)) -> u8 {
+    let mut code = 0;
+    if x < x_min {
+        code |= LEFT;
+    } else if x > x_max {
+        code |= RIGHT;
+    }
+    if y < y_min {
+        code |= TOP;
+    } else if y > y_max {
+        code |= BOTTOM;
+    }
+    code
+}
+
+/// Cohen–Sutherland clipping of the segment `a`–`b`, `None` if it misses `rect`. Segments
+/// entirely inside or outside are decided by their endpoints' outcodes alone.
+pub fn clip_line_cohen_sutherland(
+    mut a: Vertex,
+    mut b: Vertex,
+    rect: &Rect,
+) -> Option<(Vertex, Vertex)> {
+    let bounds = window(rect)?;
+    let (x_min, y_min, x_max, y_max) = bounds;
+    let (mut code_a, mut code_b) = (outcode(a, bounds), outcode(b, bounds));
+    loop {
+        if code_a | code_b == 0 {
+            return Some((a, b));
+        }
+        if code_a & code_b != 0 {
+            return None;
+        }
+        // Move an endpoint that is outside onto the border it is beyond.
+        let code = if code_a != 0 { code_a } else { code_b };
+        let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+        let p = if code & TOP != 0 {
+            (a.0 + dx * (y_min - a.1) / dy, y_min)
+        } else if code & BOTTOM != 0 {
+            (a.0 + dx * (y_max - a.1) / dy, y_max)
+        } else if code & LEFT != 0 {
+            (x_min, a.1 + dy * (x_min - a.0) / dx)
+        } else {
+            (x_max, a.1 + dy * (x_max - a.0) / dx)
+        };
+        if code == code_a {
+            a = p;
+            code_a = outcode(a, bounds);
+        } else {
+            b = p;
+            code_b = outcode(b, bounds);
+        }
+    }
+}
+
+/// The range of `t` in `0.0..=1.0` for which `a + t * (b - a)` is within `rect`.
+fn liang_barsky((ax, ay): Vertex, (bx, by): Vertex, rect: &Rect) -> Option<(f64, f64)> {
+    let (x_min, y_min, x_max, y_max) = window(rect)?;
+    let (dx, dy) = (bx - ax, by - ay);
+    let (mut t0, mut t1) = (0., 1.);
+    for (p, q) in [
+        (-dx, ax - x_min),
+        (dx, x_max - ax),
+        (-dy, ay - y_min),
+        (dy, y_max - ay),
+    ] {
+        if p == 0. {
+            // Parallel to this border, and entirely outside it.
+            if q < 0. {
+                return None;
+            }
+        } else if p < 0. {
+            t0 = f64::max(t0, q / p);
+        } else {
+            t1 = f64::min(t1, q / p);
+        }
+    }
+    if t0 > t1 {
+        return None;
+    }
+    Some((t0, t1))
+}
+
+/// Liang–Barsky clipping of the segment `a`–`b`, `None` if it misses `rect`. It does the same
+/// as `clip_line_cohen_sutherland` with a single pass over the four borders.
+pub fn clip_line_liang_barsky(a: Vertex, b: Vertex, rect: &Rect) -> Option<(Vertex, Vertex)> {
+    let (t0, t1) = liang_barsky(a, b, rect)?;
+    let at = |t: f64| (a.0 + t * (b.0 - a.0), a.1 + t * (b.1 - a.1));
+    Some((
+        if t0 > 0. { at(t0) } else { a },
+        if t1 < 1. { at(t1) } else { b },
+    ))
+}
+
+/// One Sutherland–Hodgman pass: keeps the part of `polygon` where `line.eval` is non-negative.
+fn clip_half_plane(polygon: &[Vertex], line: vector::Line<f64>) -> Vec<Vertex> {
+    let mut ret = Vec::with_capacity(polygon.len() + 1);
+    let side = |p: Vertex| line.eval(p.into());
+    for (&a, &b) in polygon.iter().zip(polygon.iter().cycle().skip(1)) {
+        let (da, db) = (side(a), side(b));
+        if da >= 0. {
+            ret.push(a);
+        }
+        if (da >= 0.) != (db >= 0.) {
+            let t = da / (da - db);
+            ret.push((a.0 + t * (b.0 - a.0), a.1 + t * (b.1 - a.1)));
+        }
+    }
+    ret
+}
+
+/// Sutherland–Hodgman clipping of `polygon` to `rect`. Concave polygons stay connected, with
+/// zero-width edges along the border where they left and re-entered it. Empty if nothing is
+/// left.
+pub fn clip_polygon(polygon: &[Vertex], rect: &Rect) -> Vec<Vertex> {
+    let (x_min, y_min, x_max, y_max) = match window(rect) {
+        Some(w) => w,
+        None => return vec![],
+    };
+    [
+        vector::Line::new(1., 0., -x_min),
+        vector::Line::new(-1., 0., x_max),
+        vector::Line::new(0., 1., -y_min),
+        vector::Line::new(0., -1., y_max),
+    ]
+    .into_iter()
+    .fold(polygon.to_vec(), |p, border| clip_half_plane(&p, border))
+}
+
+/// Sutherland–Hodgman clipping of `polygon` to the convex polygon `window`, which may run in
+/// either direction. Empty if nothing is left or `window` has no area.
+pub fn clip_polygon_convex(polygon: &[Vertex], window: &[Vertex]) -> Vec<Vertex> {
+    let area: f64 = window
+        .iter()
+        .zip(window.iter().cycle().skip(1))
+        .map(|(a, b)| a.0 * b.1 - b.0 * a.1)
+        .sum();
+    if area == 0. {
+        return vec![];
+    }
+    let mut ret = polygon.to_vec();
+    for (&a, &b) in window.iter().zip(window.iter().cycle().skip(1)) {
+        let border = match vector::Line::through(a.into(), b.into()) {
+            Some(l) => l,
+            None => continue,
+        };
+        // The normal of `Line::through` points right of a→b on screen, inside a clockwise window.
+        let border = if area > 0. {
+            border
+        } else {
+            vector::Line::new(-border.a, -border.b, -border.c)
+        };
+        ret = clip_half_plane(&ret, border);
+        if ret.is_empty() {
+            break;
+        }
+    }
+    ret
+}
+
+impl Image {
+    /// The pixels that drawing may change: the image bounds, narrowed by `clip` if it is set.
+    pub fn clip_rect(&self) -> Rect {
+        let bounds = Rect {
+            x: 0,
+            y: 0,
+            width: self.width,
+            height: self.height,
+        };
+        match self.clip {
+            Some(clip) => bounds.intersection(&clip).unwrap_or(Rect {
+                width: 0,
+                height: 0,
+                ..bounds
+            }),
+            None => bounds,
+        }
+    }
+
+    /// Whether plotting at `(x, y)` changes the image.
+    pub fn is_visible(&self, p: Point) -> bool {
+        self.clip_rect().contains(p)
+    }
+
+    /// The steps along the major axis of the line `a`–`b` whose pixels may be visible, for
+    /// rasterizers that only walk the part of a line inside `clip_rect`.
+    pub(crate) fn visible_steps(&self, a: Point, b: Point) -> Option<RangeInclusive<i64>> {
+        let rect = self.clip_rect();
+        let major = (b.0 - a.0).abs().max((b.1 - a.1).abs());
+        if rect.contains(a) && rect.contains(b) {
+            return Some(0..=major);
+        }
+        let (t0, t1) = liang_barsky((a.0 as f64, a.1 as f64), (b.0 as f64, b.1 as f64), &rect)?;
+        // A step's pixel is up to half a pixel off the line, so one more is kept at each end.
+        let first = ((t0 * major as f64).floor() as i64 - 1).max(0);
+        let last = ((t1 * major as f64).ceil() as i64 + 1).min(major);
+        Some(first..=last)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RECT: Rect = Rect {
+        x: 2,
+        y: 3,
+        width: 10,
+        height: 6,
+    };
+
+    #[test]
+    fn clippers_agree() {
+        let close = |a: Vertex, b: Vertex| (a.0 - b.0).abs() < 1e-9 && (a.1 - b.1).abs() < 1e-9;
+        let coords = [-3., 0., 1.5, 4., 7.25, 11.5, 16.];
+        for &ax in &coords {
+            for &ay in &coords {
+                for &bx in &coords {
+                    for &by in &coords {
+                        let (a, b) = ((ax, ay), (bx, by));
+                        let cs = clip_line_cohen_sutherland(a, b, &RECT);
+                        let lb = clip_line_liang_barsky(a, b, &RECT);
+                        match (cs, lb) {
+                            (Some(cs), Some(lb)) => {
+                                assert!(close(cs.0, lb.0) && close(cs.1, lb.1), "{a:?} {b:?}");
+                            }
+                            (None, None) => {}
+                            _ => panic!("{a:?}–{b:?}: {cs:?} and {lb:?}"),
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn lines() {
+        // The window runs from the outer edges of its border pixels.
+        assert_eq!(
+            clip_line_liang_barsky((0., 5.), (20., 5.), &RECT),
+            Some(((1.5, 5.), (11.5, 5.)))
+        );
+        assert_eq!(
+            clip_line_cohen_sutherland((5., 0.), (5., 20.), &RECT),
+            Some(((5., 2.5), (5., 8.5)))
+        );
+        assert_eq!(
+            clip_line_liang_barsky((3., 4.), (6., 7.), &RECT),
+            Some(((3., 4.), (6., 7.)))
+        );
+        assert_eq!(clip_line_liang_barsky((0., 0.), (20., 0.), &RECT), None);
+        // Crosses the corner's outcode regions without entering the window.
+        assert_eq!(
+            clip_line_cohen_sutherland((0., 3.5), (3.5, 0.), &RECT),
+            None
+        );
+        let empty = Rect { width: 0, ..RECT };
+        assert_eq!(clip_line_liang_barsky((3., 4.), (6., 7.), &empty), None);
+    }
+
+    fn area(polygon: &[Vertex]) -> f64 {
+        polygon
+            .iter()
+            .zip(polygon.iter().cycle().skip(1))
+            .map(|(a, b)| a.0 * b.1 - b.0 * a.1)
+            .sum::<f64>()
+            .abs()
+            / 2.
+    }
+
+    #[test]
+    fn polygons() {
+        let big = [(0., 0.), (20., 0.), (20., 20.), (0., 20.)];
+        assert_eq!(area(&clip_polygon(&big, &RECT)), 60.);
+        let inside = [(3., 4.), (6., 4.), (6., 7.)];
+        assert_eq!(clip_polygon(&inside, &RECT), inside);
+        let outside = [(20., 0.), (30., 0.), (30., 10.)];
+        assert!(clip_polygon(&outside, &RECT).is_empty());
+
+        // A window traversed in either direction clips the same way.
+        let diamond = [(5., 0.), (10., 5.), (5., 10.), (0., 5.)];
+        let reversed = diamond.iter().rev().copied().collect::<Vec<_>>();
+        let square = [(0., 0.), (5., 0.), (5., 5.), (0., 5.)];
+        assert_eq!(area(&clip_polygon_convex(&square, &diamond)), 12.5);
+        assert_eq!(area(&clip_polygon_convex(&square, &reversed)), 12.5);
+        assert!(clip_polygon_convex(&square, &[(0., 0.), (1., 1.), (2., 2.)]).is_empty());
+    }
+
+    #[test]
+    fn clipped_hairlines() {
+        for (a, b) in [((-7, 1), (30, 9)), ((4, -20), (9, 25)), ((15, 15), (-3, 0))] {
+            let mut full = Image::new(16, 12, 0, 0);
+            full.plot_line_width(a, b, 1.);
+            let mut clipped = Image::new(16, 12, 0, 0);
+            clipped.clip = Some(RECT);
+            assert_eq!(clipped.clip_rect(), RECT);
+            clipped.plot_line_width(a, b, 1.);
+            for y in 0..12 {
+                for x in 0..16 {
+                    let expected = if RECT.contains((x, y)) {
+                        full.get(x, y)
+                    } else {
+                        Some(crate::WHITE)
+                    };
+                    assert_eq!(clipped.get(x, y), expected, "({x}, {y})");
+                }
+            }
+        }
+    }
+}
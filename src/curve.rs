@@ -0,0 +1,216 @@
+//! Space-filling curves, as orders in which to visit every pixel of an image. Consecutive
+//! pixels along either curve tend to be close to each other in the image, unlike in reading
+//! order.
+
+use crate::Point;
+
+/// The pixels of a `width` × `height` image along a Hilbert curve, from the top left corner.
+/// Any size works, by Jakub Červený's generalization: the image is split in halves or thirds
+/// whose sides aren't powers of two either. Each pixel is a neighbor of the one before, except
+/// for a single diagonal step in some images with one side even and the other odd.
+pub struct Hilbert {
+    points: std::vec::IntoIter<Point>,
+}
+
+impl Hilbert {
+    pub fn new(width: usize, height: usize) -> Self {
+        let (w, h) = (width as i64, height as i64);
+        let mut points = Vec::with_capacity(width * height);
+        if w > 0 && h > 0 {
+            if w >= h {
+                hilbert(&mut points, (0, 0), (w, 0), (0, h));
+            } else {
+                hilbert(&mut points, (0, 0), (0, h), (w, 0));
+            }
+        }
+        Self {
+            points: points.into_iter(),
+        }
+    }
+}
+
+impl Iterator for Hilbert {
+    type Item = Point;
+
+    fn next(&mut self) -> Option<Point> {
+        self.points.next()
+    }
+}
+
+/// Fills the rectangle from `(x, y)` spanned by the major axis `a` and the minor axis `b`.
+fn hilbert(points: &mut Vec<Point>, (x, y): Point, (ax, ay): Point, (bx, by): Point) {
+    let (w, h) = ((ax + ay).abs(), (bx + by).abs());
+    let (dax, day) = (ax.signum(), ay.signum());
+    let (dbx, dby) = (bx.signum(), by.signum());
+    if h == 1 {
+        points.extend((0..w).map(|i| (x + i * dax, y + i * day)));
+        return;
+    }
+    if w == 1 {
+        points.extend((0..h).map(|i| (x + i * dbx, y + i * dby)));
+        return;
+    }
+    let (mut ax2, mut ay2) = (ax.div_euclid(2), ay.div_euclid(2));
+    let (mut bx2, mut by2) = (bx.div_euclid(2), by.div_euclid(2));
+    if 2 * w > 3 * h {
+        // Too long for its width: split in two along the major axis, with even halves when
+        // possible.
+        if (ax2 + ay2).abs() % 2 == 1 && w > 2 {
+            (ax2, ay2) = (ax2 + dax, ay2 + day);
+        }
+        hilbert(points, (x, y), (ax2, ay2), (bx, by));
+        hilbert(points, (x + ax2, y + ay2), (ax - ax2, ay - ay2), (bx, by));
+    } else {
+        // Split in three, like the U of the plain Hilbert curve: into the near half of the
+        // minor axis on one side, across the whole major axis, and back out on the other side.
+        if (bx2 + by2).abs() % 2 == 1 && h > 2 {
+            (bx2, by2) = (bx2 + dbx, by2 + dby);
+        }
+        hilbert(points, (x, y), (bx2, by2), (ax2, ay2));
+        hilbert(points, (x + bx2, y + by2), (ax, ay), (bx - bx2, by - by2));
+        hilbert(
+            points,
+            (x + (ax - dax) + (bx2 - dbx), y + (ay - day) + (by2 - dby)),
+            (-bx2, -by2),
+            (-(ax - ax2), -(ay - ay2)),
+        );
+    }
+}
+
+/// The pixels of a `width` × `height` image in Z-order (Morton order), which visits each
+/// quadrant in turn, top left, top right, bottom left, bottom right, and the same way within
+/// it. Sizes that aren't powers of two are covered by skipping the pixels outside the image.
+pub struct ZOrder {
+    width: usize,
+    height: usize,
+    /// How many low bits of x and y are interleaved. The remaining bits of the longer side, x
+    /// when `wide`, come above them.
+    shared_bits: u32,
+    wide: bool,
+    index: u64,
+    end: u64,
+}
+
+impl ZOrder {
+    pub fn new(width: usize, height: usize) -> Self {
+        let bits = |n: usize| n.next_power_of_two().trailing_zeros();
+        let (x_bits, y_bits) = (bits(width), bits(height));
+        Self {
+            width,
+            height,
+            shared_bits: x_bits.min(y_bits),
+            wide: x_bits > y_bits,
+            index: 0,
+            end: if width == 0 || height == 0 {
+                0
+            } else {
+                1 << (x_bits + y_bits)
+            },
+        }
+    }
+}
+
+impl Iterator for ZOrder {
+    type Item = Point;
+
+    fn next(&mut self) -> Option<Point> {
+        while self.index < self.end {
+            let i = self.index;
+            self.index += 1;
+            let (mut x, mut y) = (0, 0);
+            for bit in 0..self.shared_bits {
+                x |= ((i >> (2 * bit)) & 1) << bit;
+                y |= ((i >> (2 * bit + 1)) & 1) << bit;
+            }
+            let rest = (i >> (2 * self.shared_bits)) << self.shared_bits;
+            if self.wide {
+                x |= rest;
+            } else {
+                y |= rest;
+            }
+            if (x as usize) < self.width && (y as usize) < self.height {
+                return Some((x as i64, y as i64));
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Whether `points` holds every pixel of a `width` × `height` image exactly once.
+    fn covers(points: &[Point], width: usize, height: usize) -> bool {
+        let mut seen = vec![false; width * height];
+        for (x, y) in points {
+            if *x < 0 || *y < 0 || *x as usize >= width || *y as usize >= height {
+                return false;
+            }
+            let i = *y as usize * width + *x as usize;
+            if seen[i] {
+                return false;
+            }
+            seen[i] = true;
+        }
+        seen.into_iter().all(|s| s)
+    }
+
+    #[test]
+    fn hilbert() {
+        assert_eq!(
+            Hilbert::new(2, 2).collect::<Vec<_>>(),
+            [(0, 0), (0, 1), (1, 1), (1, 0)]
+        );
+        assert_eq!(Hilbert::new(0, 5).count(), 0);
+        for width in 1..=20 {
+            for height in 1..=20 {
+                let points = Hilbert::new(width, height).collect::<Vec<_>>();
+                assert!(covers(&points, width, height), "{width}×{height}");
+                assert_eq!(points[0], (0, 0));
+                let diagonals = points
+                    .windows(2)
+                    .map(|p| ((p[0].0 - p[1].0).abs(), (p[0].1 - p[1].1).abs()))
+                    .inspect(|d| assert!(d.0 <= 1 && d.1 <= 1, "{width}×{height}"))
+                    .filter(|d| *d == (1, 1))
+                    .count();
+                if width % 2 == height % 2 {
+                    assert_eq!(diagonals, 0, "{width}×{height}");
+                } else {
+                    assert!(diagonals <= 1, "{width}×{height}");
+                }
+            }
+        }
+        // Square powers of two end in the top right corner, like the plain Hilbert curve.
+        assert_eq!(Hilbert::new(16, 16).last(), Some((15, 0)));
+    }
+
+    #[test]
+    fn z_order() {
+        assert_eq!(
+            ZOrder::new(4, 4).take(6).collect::<Vec<_>>(),
+            [(0, 0), (1, 0), (0, 1), (1, 1), (2, 0), (3, 0)]
+        );
+        // The pixels past a wide image's square part come after it.
+        assert_eq!(
+            ZOrder::new(4, 2).collect::<Vec<_>>(),
+            [
+                (0, 0),
+                (1, 0),
+                (0, 1),
+                (1, 1),
+                (2, 0),
+                (3, 0),
+                (2, 1),
+                (3, 1)
+            ]
+        );
+        assert_eq!(ZOrder::new(3, 0).count(), 0);
+        for width in 1..=20 {
+            for height in 1..=20 {
+                let points = ZOrder::new(width, height).collect::<Vec<_>>();
+                assert!(covers(&points, width, height), "{width}×{height}");
+            }
+        }
+    }
+}
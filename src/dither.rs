@@ -0,0 +1,916 @@
+//! Dithering to `BLACK` and `WHITE`. Error diffusion sets each pixel and spreads the difference
+//! to its gray level over the neighbors that haven't been visited yet, according to a kernel.
+//! Ordered dithering compares each pixel with a threshold map tiled over the image, so the
+//! result doesn't depend on the rest of the image and stays put when other pixels change.
+//! Riemersma's method diffuses error along a Hilbert curve instead.
+
+use std::f64::consts::TAU;
+
+use crate::curve::Hilbert;
+use crate::rng::XorShift;
+use crate::{from_u32_rgb, from_u8_rgb, Image, BLACK, WHITE};
+
+/// How the error of a pixel is spread over its neighbors.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Kernel {
+    weights: Vec<(i64, i64, f64)>,
+}
+
+impl Kernel {
+    /// The kernel giving `weight / divisor` of the error to each offset `(dx, dy)`. Returns
+    /// `None` if `divisor` is zero, if the weights add up to more than `divisor`, which would
+    /// make errors grow as they spread, or if an offset points at a pixel that has already
+    /// been visited, that is if `dy` is negative, or `dy` is zero and `dx` isn't positive.
+    pub fn new(weights: &[(i64, i64, u32)], divisor: u32) -> Option<Self> {
+        if divisor == 0
+            || weights.iter().map(|(_, _, w)| *w as u64).sum::<u64>() > divisor as u64
+            || weights
+                .iter()
+                .any(|(dx, dy, _)| *dy < 0 || (*dy == 0 && *dx <= 0))
+        {
+            return None;
+        }
+        Some(Self::table(weights, divisor))
+    }
+
+    /// `new` without the checks, for the built-in kernels.
+    fn table(weights: &[(i64, i64, u32)], divisor: u32) -> Self {
+        Self {
+            weights: weights
+                .iter()
+                .map(|(dx, dy, w)| (*dx, *dy, *w as f64 / divisor as f64))
+                .collect(),
+        }
+    }
+
+    /// The share of the error going to the pixel `(dx, dy)` away, for each offset.
+    pub fn weights(&self) -> &[(i64, i64, f64)] {
+        &self.weights
+    }
+
+    pub fn floyd_steinberg() -> Self {
+        Self::table(&[(1, 0, 7), (-1, 1, 3), (0, 1, 5), (1, 1, 1)], 16)
+    }
+
+    /// Spreads only three quarters of the error, which keeps contrast at the cost of detail in
+    /// highlights and shadows.
+    pub fn atkinson() -> Self {
+        Self::table(
+            &[
+                (1, 0, 1),
+                (2, 0, 1),
+                (-1, 1, 1),
+                (0, 1, 1),
+                (1, 1, 1),
+                (0, 2, 1),
+            ],
+            8,
+        )
+    }
+
+    pub fn jarvis_judice_ninke() -> Self {
+        Self::table(
+            &[
+                (1, 0, 7),
+                (2, 0, 5),
+                (-2, 1, 3),
+                (-1, 1, 5),
+                (0, 1, 7),
+                (1, 1, 5),
+                (2, 1, 3),
+                (-2, 2, 1),
+                (-1, 2, 3),
+                (0, 2, 5),
+                (1, 2, 3),
+                (2, 2, 1),
+            ],
+            48,
+        )
+    }
+
+    pub fn stucki() -> Self {
+        Self::table(
+            &[
+                (1, 0, 8),
+                (2, 0, 4),
+                (-2, 1, 2),
+                (-1, 1, 4),
+                (0, 1, 8),
+                (1, 1, 4),
+                (2, 1, 2),
+                (-2, 2, 1),
+                (-1, 2, 2),
+                (0, 2, 4),
+                (1, 2, 2),
+                (2, 2, 1),
+            ],
+            42,
+        )
+    }
+
+    pub fn burkes() -> Self {
+        Self::table(
+            &[
+                (1, 0, 8),
+                (2, 0, 4),
+                (-2, 1, 2),
+                (-1, 1, 4),
+                (0, 1, 8),
+                (1, 1, 4),
+                (2, 1, 2),
+            ],
+            32,
+        )
+    }
+
+    /// Sierra's three-row kernel.
+    pub fn sierra() -> Self {
+        Self::table(
+            &[
+                (1, 0, 5),
+                (2, 0, 3),
+                (-2, 1, 2),
+                (-1, 1, 4),
+                (0, 1, 5),
+                (1, 1, 4),
+                (2, 1, 2),
+                (-1, 2, 2),
+                (0, 2, 3),
+                (1, 2, 2),
+            ],
+            32,
+        )
+    }
+
+    pub fn two_row_sierra() -> Self {
+        Self::table(
+            &[
+                (1, 0, 4),
+                (2, 0, 3),
+                (-2, 1, 1),
+                (-1, 1, 2),
+                (0, 1, 3),
+                (1, 1, 2),
+                (2, 1, 1),
+            ],
+            16,
+        )
+    }
+
+    /// Sierra's smallest kernel, also known as Sierra-2-4A.
+    pub fn sierra_lite() -> Self {
+        Self::table(&[(1, 0, 2), (-1, 1, 1), (0, 1, 1)], 4)
+    }
+
+    /// How many rows below the current one receive error.
+    fn depth(&self) -> usize {
+        self.weights
+            .iter()
+            .map(|(_, dy, _)| *dy as usize)
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+/// A binary pattern on a torus, with the energy of each cell: the sum over the cells that are
+/// on of a Gaussian of their distance. Clusters have high energy and voids low.
+#[derive(Clone)]
+struct Pattern {
+    size: usize,
+    kernel: Vec<f64>,
+    on: Vec<bool>,
+    energy: Vec<f64>,
+}
+
+impl Pattern {
+    fn new(size: usize) -> Self {
+        let wrap = |v: usize| v.min(size - v) as f64;
+        let kernel = (0..size * size)
+            .map(|i| {
+                let (dx, dy) = (wrap(i % size), wrap(i / size));
+                (-(dx * dx + dy * dy) / (2. * 1.5 * 1.5)).exp()
+            })
+            .collect();
+        Self {
+            size,
+            kernel,
+            on: vec![false; size * size],
+            energy: vec![0.; size * size],
+        }
+    }
+
+    fn toggle(&mut self, i: usize) {
+        self.on[i] = !self.on[i];
+        let sign = if self.on[i] { 1. } else { -1. };
+        let (x, y, n) = (i % self.size, i / self.size, self.size);
+        for (j, e) in self.energy.iter_mut().enumerate() {
+            let (dx, dy) = ((j % n + n - x) % n, (j / n + n - y) % n);
+            *e += sign * self.kernel[dy * n + dx];
+        }
+    }
+
+    /// The cell that is on with the most energy.
+    fn tightest_cluster(&self) -> usize {
+        (0..self.on.len())
+            .filter(|i| self.on[*i])
+            .max_by(|a, b| self.energy[*a].total_cmp(&self.energy[*b]))
+            .unwrap()
+    }
+
+    /// The cell that is off with the least energy.
+    fn largest_void(&self) -> usize {
+        (0..self.on.len())
+            .filter(|i| !self.on[*i])
+            .min_by(|a, b| self.energy[*a].total_cmp(&self.energy[*b]))
+            .unwrap()
+    }
+}
+
+/// Thresholds in `0.0..1.0`, row by row, tiled over the image by ordered dithering. Maps are
+/// never empty.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ThresholdMap {
+    width: usize,
+    height: usize,
+    values: Vec<f64>,
+}
+
+impl ThresholdMap {
+    /// Returns `None` if the map is empty or `values` doesn't hold `width * height` thresholds.
+    pub fn new(width: usize, height: usize, values: Vec<f64>) -> Option<Self> {
+        if width == 0 || height == 0 || width.checked_mul(height) != Some(values.len()) {
+            return None;
+        }
+        Some(Self {
+            width,
+            height,
+            values,
+        })
+    }
+
+    /// The map that turns on the cells in the order of `ranks`, with the levels evenly spaced.
+    /// Returns `None` unless `ranks` is a permutation of `0..width * height`.
+    pub fn from_ranks(width: usize, height: usize, ranks: &[usize]) -> Option<Self> {
+        let mut seen = vec![false; ranks.len()];
+        for r in ranks {
+            if *seen.get(*r)? {
+                return None;
+            }
+            seen[*r] = true;
+        }
+        let n = ranks.len() as f64;
+        Self::new(
+            width,
+            height,
+            ranks.iter().map(|r| (*r as f64 + 0.5) / n).collect(),
+        )
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// The thresholds, row by row.
+    pub fn values(&self) -> &[f64] {
+        &self.values
+    }
+
+    /// The map ranking the cells by `key`, lowest first, with ties broken in reading order.
+    fn sorted_by(size: usize, key: impl Fn(f64, f64) -> f64) -> Option<Self> {
+        let mut cells = (0..size * size).collect::<Vec<_>>();
+        let keys = cells
+            .iter()
+            .map(|i| key((i % size) as f64, (i / size) as f64))
+            .collect::<Vec<_>>();
+        cells.sort_by(|a, b| keys[*a].total_cmp(&keys[*b]));
+        let mut ranks = vec![0; size * size];
+        for (rank, cell) in cells.into_iter().enumerate() {
+            ranks[cell] = rank;
+        }
+        Self::from_ranks(size, size, &ranks)
+    }
+
+    /// Bayer's dispersed-dot matrix of a power of two `size`, where every level spreads its
+    /// pixels as evenly as possible. Returns `None` for other sizes.
+    pub fn bayer(size: usize) -> Option<Self> {
+        if !size.is_power_of_two() {
+            return None;
+        }
+        let mut ranks = vec![0];
+        let mut n = 1;
+        while n < size {
+            // The doubled matrix is four copies of this one, in the order 0, 2, 3, 1.
+            let mut next = vec![0; 4 * n * n];
+            for y in 0..n {
+                for x in 0..n {
+                    let r = 4 * ranks[y * n + x];
+                    for (dx, dy, offset) in [(0, 0, 0), (1, 0, 2), (0, 1, 3), (1, 1, 1)] {
+                        next[(y + dy * n) * 2 * n + x + dx * n] = r + offset;
+                    }
+                }
+            }
+            ranks = next;
+            n *= 2;
+        }
+        Self::from_ranks(size, size, &ranks)
+    }
+
+    /// A clustered-dot screen of `size` × `size` cells, as in printed halftones. Brighter
+    /// levels grow a round white dot from the middle of the tile, so that the black left over
+    /// shrinks towards the corners. Returns `None` if `size` is zero.
+    pub fn clustered_dot(size: usize) -> Option<Self> {
+        let c = (size as f64 - 1.) / 2.;
+        Self::sorted_by(size, |x, y| {
+            (x - c).hypot(y - c) + (y - c).atan2(x - c).rem_euclid(TAU) * 1e-6
+        })
+    }
+
+    /// A clustered-dot screen at 45°: the tile holds a dot in the middle and one on its
+    /// corners, which grow in turn, making the screen less visible than `clustered_dot`.
+    /// Returns `None` if `size` is zero.
+    pub fn diagonal_clustered_dot(size: usize) -> Option<Self> {
+        let c = (size as f64 - 1.) / 2.;
+        Self::sorted_by(size, |x, y| {
+            let middle = (x - c).hypot(y - c);
+            // The distance to the nearest corner of the tile, which its four neighbors share.
+            let corner = (c + 0.5 - (x - c).abs()).hypot(c + 0.5 - (y - c).abs());
+            // The two dots grow in turn, the middle one first.
+            middle.min(corner + 1e-9)
+        })
+    }
+
+    /// A blue-noise map made with Ulichney's void-and-cluster method. Every level spreads its
+    /// pixels evenly without the regular texture of `bayer`, and the map tiles seamlessly. The
+    /// same `seed` always gives the same map. Takes time proportional to `size` to the fourth
+    /// power, so larger maps are better made once and kept with `to_image`. Returns `None` if
+    /// `size` is zero.
+    pub fn blue_noise(size: usize, seed: u64) -> Option<Self> {
+        if size == 0 {
+            return None;
+        }
+        let n = size * size;
+        let mut pattern = Pattern::new(size);
+        let mut cells = (0..n).collect::<Vec<_>>();
+        XorShift::new(seed).shuffle(&mut cells);
+        let ones = (n / 10).max(1);
+        for i in &cells[..ones] {
+            pattern.toggle(*i);
+        }
+        // Move pixels from the tightest cluster to the largest void until the pattern settles.
+        for _ in 0..n {
+            let cluster = pattern.tightest_cluster();
+            pattern.toggle(cluster);
+            let void = pattern.largest_void();
+            pattern.toggle(void);
+            if void == cluster {
+                break;
+            }
+        }
+        let mut ranks = vec![0; n];
+        // Below the initial pattern, remove pixels from the tightest clusters first.
+        let mut fewer = pattern.clone();
+        for rank in (0..ones).rev() {
+            let cluster = fewer.tightest_cluster();
+            fewer.toggle(cluster);
+            ranks[cluster] = rank;
+        }
+        // Above it, fill the largest voids first. Past half way, this also picks the tightest
+        // cluster of the pixels still off.
+        for rank in ones..n {
+            let void = pattern.largest_void();
+            pattern.toggle(void);
+            ranks[void] = rank;
+        }
+        Self::from_ranks(size, size, &ranks)
+    }
+
+    /// The map whose thresholds are the gray levels of `image`, as measured by
+    /// `DitherOptions::default()`, so that a pixel becomes `WHITE` where it is brighter than
+    /// the map. Returns `None` if the image is empty.
+    pub fn from_image(image: &Image) -> Option<Self> {
+        let options = DitherOptions::default();
+        Self::new(
+            image.width,
+            image.height,
+            image
+                .bytes
+                .iter()
+                .map(|p| ((options.gray(*p) * 255.).round() + 0.5) / 256.)
+                .collect(),
+        )
+    }
+
+    /// The map as a gray image, which `from_image` turns back into the map when it has no
+    /// more than 256 levels.
+    pub fn to_image(&self) -> Image {
+        let mut ret = Image::new(self.width, self.height, 0, 0);
+        for (p, t) in ret.bytes.iter_mut().zip(&self.values) {
+            let v = (t * 256. - 0.5).round().clamp(0., 255.) as u8;
+            *p = from_u8_rgb(v, v, v);
+        }
+        ret
+    }
+
+    /// The threshold at `(x, y)` of the tiled map.
+    pub fn get(&self, x: usize, y: usize) -> f64 {
+        self.values[(y % self.height) * self.width + x % self.width]
+    }
+}
+
+/// The weights of red, green and blue in the brightness of a color.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Luma {
+    /// `0.299, 0.587, 0.114`, as for standard-definition video.
+    Rec601,
+    /// `0.2126, 0.7152, 0.0722`, as for HDTV and sRGB. With `linearize`, this gives the
+    /// relative luminance.
+    Rec709,
+    /// Weights of red, green and blue, which should add up to one.
+    Custom(f64, f64, f64),
+}
+
+impl Luma {
+    fn weights(self) -> (f64, f64, f64) {
+        match self {
+            Luma::Rec601 => (0.299, 0.587, 0.114),
+            Luma::Rec709 => (0.2126, 0.7152, 0.0722),
+            Luma::Custom(r, g, b) => (r, g, b),
+        }
+    }
+}
+
+/// Converts an sRGB channel in `0.0..=1.0` to linear light.
+fn srgb_to_linear(c: f64) -> f64 {
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// How pixels are turned into gray levels and visited when dithering.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DitherOptions {
+    /// Scans every other row right to left, with the kernel mirrored, which breaks up the
+    /// diagonal "worms" of a single scan direction.
+    pub serpentine: bool,
+    /// Converts sRGB values to linear light before weighing them, so that the density of
+    /// `WHITE` pixels matches the brightness of the original.
+    pub linearize: bool,
+    pub luma: Luma,
+    /// Gray levels above this become `WHITE`.
+    pub threshold: f64,
+    /// Limits the error a pixel may receive from its neighbors to this magnitude, which stops
+    /// errors from piling up and smearing across edges. Areas closer to black or white than
+    /// the limit lose their isolated dots.
+    pub error_clamp: Option<f64>,
+}
+
+impl Default for DitherOptions {
+    fn default() -> Self {
+        Self {
+            serpentine: false,
+            linearize: false,
+            luma: Luma::Rec601,
+            threshold: 0.5,
+            error_clamp: None,
+        }
+    }
+}
+
+impl DitherOptions {
+    /// The brightness of `color` in `0.0..=1.0`.
+    pub fn gray(&self, color: u32) -> f64 {
+        let (r, g, b) = from_u32_rgb(color);
+        let channel = |c: u8| {
+            let c = c as f64 / 255.;
+            if self.linearize {
+                srgb_to_linear(c)
+            } else {
+                c
+            }
+        };
+        let (wr, wg, wb) = self.luma.weights();
+        wr * channel(r) + wg * channel(g) + wb * channel(b)
+    }
+}
+
+impl Image {
+    /// Turns the image into `BLACK` and `WHITE` pixels by diffusing the error of each pixel
+    /// with `kernel`, scanning left to right and top to bottom.
+    pub fn dither(&self, kernel: &Kernel) -> Image {
+        self.dither_with(kernel, &DitherOptions::default())
+    }
+
+    /// Ordered dithering with `map`, tiled from the image's top left corner. Only the gray
+    /// conversion of `options` applies.
+    pub fn dither_ordered(&self, map: &ThresholdMap, options: &DitherOptions) -> Image {
+        let mut ret = Image::new(self.width, self.height, self.x_offset, self.y_offset);
+        ret.hotspot = self.hotspot;
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let i = y * self.width + x;
+                ret.bytes[i] = if options.gray(self.bytes[i]) > map.get(x, y) {
+                    WHITE
+                } else {
+                    BLACK
+                };
+            }
+        }
+        ret
+    }
+
+    /// Riemersma's dithering, which visits the pixels along a Hilbert curve and adds to each
+    /// the errors of the last 16 pixels before it, weighed so that the oldest counts a
+    /// sixteenth as much as the newest. As the error stays on the curve, there are no
+    /// directional artifacts, and it never travels far, though it is also forgotten before
+    /// the sparsest dots of nearly black or white areas are placed. The scan order of
+    /// `options` doesn't apply.
+    pub fn dither_riemersma(&self, options: &DitherOptions) -> Image {
+        const HISTORY: usize = 16;
+        let weights = (0..HISTORY)
+            .map(|i| (16f64.ln() * i as f64 / (HISTORY - 1) as f64).exp() / 16.)
+            .collect::<Vec<_>>();
+        let mut ret = Image::new(self.width, self.height, self.x_offset, self.y_offset);
+        ret.hotspot = self.hotspot;
+        // The errors of the pixels visited last, the newest at the end.
+        let mut history = vec![0.; HISTORY];
+        for (x, y) in Hilbert::new(self.width, self.height) {
+            let i = y as usize * self.width + x as usize;
+            let error = weights
+                .iter()
+                .zip(&history)
+                .map(|(w, e)| w * e)
+                .sum::<f64>();
+            let error = match options.error_clamp {
+                Some(limit) => error.clamp(-limit, limit),
+                None => error,
+            };
+            let value = options.gray(self.bytes[i]) + error;
+            let (color, target) = if value > options.threshold {
+                (WHITE, 1.)
+            } else {
+                (BLACK, 0.)
+            };
+            ret.bytes[i] = color;
+            history.rotate_left(1);
+            history[HISTORY - 1] = options.gray(self.bytes[i]) - target;
+        }
+        ret
+    }
+
+    /// Like `dither`, with the scan order and the conversion to gray set by `options`. Error
+    /// that would fall outside the image is dropped.
+    pub fn dither_with(&self, kernel: &Kernel, options: &DitherOptions) -> Image {
+        let mut ret = Image::new(self.width, self.height, self.x_offset, self.y_offset);
+        ret.hotspot = self.hotspot;
+        let w = self.width as i64;
+        // The error for the current row and the ones below it that the kernel reaches.
+        let mut errors = vec![vec![0f64; self.width]; kernel.depth() + 1];
+        for y in 0..self.height {
+            let reversed = options.serpentine && y % 2 == 1;
+            for step in 0..self.width {
+                let x = if reversed {
+                    self.width - 1 - step
+                } else {
+                    step
+                };
+                let i = y * self.width + x;
+                let error = match options.error_clamp {
+                    Some(limit) => errors[0][x].clamp(-limit, limit),
+                    None => errors[0][x],
+                };
+                let value = options.gray(self.bytes[i]) + error;
+                let (color, target) = if value > options.threshold {
+                    (WHITE, 1.)
+                } else {
+                    (BLACK, 0.)
+                };
+                ret.bytes[i] = color;
+                let error = value - target;
+                for (dx, dy, weight) in &kernel.weights {
+                    let nx = if reversed {
+                        x as i64 - dx
+                    } else {
+                        x as i64 + dx
+                    };
+                    if nx >= 0 && nx < w {
+                        errors[*dy as usize][nx as usize] += error * weight;
+                    }
+                }
+            }
+            errors.rotate_left(1);
+            errors.last_mut().unwrap().fill(0.);
+        }
+        ret
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn threshold_map_constructors() {
+        assert!(ThresholdMap::new(0, 0, vec![]).is_none());
+        assert!(ThresholdMap::new(2, 1, vec![0.5]).is_none());
+        assert!(ThresholdMap::from_ranks(2, 1, &[0, 0]).is_none());
+        assert!(ThresholdMap::from_ranks(2, 1, &[0, 2]).is_none());
+        assert!(ThresholdMap::bayer(0).is_none());
+        assert!(ThresholdMap::bayer(3).is_none());
+        assert!(ThresholdMap::clustered_dot(0).is_none());
+        assert!(ThresholdMap::diagonal_clustered_dot(0).is_none());
+        assert!(ThresholdMap::blue_noise(0, 1).is_none());
+        assert!(ThresholdMap::from_image(&Image::new(0, 0, 0, 0)).is_none());
+
+        let map = ThresholdMap::from_ranks(2, 1, &[1, 0]).unwrap();
+        assert_eq!(map.values(), [0.75, 0.25]);
+        assert_eq!((map.get(0, 0), map.get(3, 5)), (0.75, 0.25));
+    }
+
+    /// A `width` × `height` image of the gray `v`.
+    fn gray(width: usize, height: usize, v: u8) -> Image {
+        Image {
+            bytes: vec![from_u8_rgb(v, v, v); width * height],
+            ..Image::new(width, height, 0, 0)
+        }
+    }
+
+    fn black_share(image: &Image) -> f64 {
+        image.black_pixels().len() as f64 / image.bytes.len() as f64
+    }
+
+    #[test]
+    fn kernels() {
+        assert!(Kernel::new(&[(1, 0, 1)], 0).is_none());
+        assert!(Kernel::new(&[(1, 0, 3), (0, 1, 2)], 4).is_none());
+        assert!(Kernel::new(&[(1, 0, u32::MAX), (0, 1, u32::MAX)], u32::MAX).is_none());
+        assert!(Kernel::new(&[(0, 0, 1)], 2).is_none());
+        assert!(Kernel::new(&[(-1, 0, 1)], 2).is_none());
+        assert!(Kernel::new(&[(3, -1, 1)], 2).is_none());
+        let kernel = Kernel::new(&[(1, 0, 1), (-2, 1, 1)], 4).unwrap();
+        assert_eq!(kernel.weights(), [(1, 0, 0.25), (-2, 1, 0.25)]);
+        assert_eq!(kernel.depth(), 1);
+        assert!(Kernel::new(&[], 1).is_some());
+
+        for kernel in [
+            Kernel::floyd_steinberg(),
+            Kernel::jarvis_judice_ninke(),
+            Kernel::stucki(),
+            Kernel::burkes(),
+            Kernel::sierra(),
+            Kernel::two_row_sierra(),
+            Kernel::sierra_lite(),
+        ] {
+            let total = kernel.weights().iter().map(|(_, _, w)| w).sum::<f64>();
+            assert!((total - 1.).abs() < 1e-12);
+        }
+        let atkinson = Kernel::atkinson();
+        assert_eq!(
+            atkinson.weights().iter().map(|(_, _, w)| w).sum::<f64>(),
+            0.75
+        );
+    }
+
+    #[test]
+    fn error_diffusion() {
+        for kernel in [
+            Kernel::floyd_steinberg(),
+            Kernel::jarvis_judice_ninke(),
+            Kernel::stucki(),
+            Kernel::burkes(),
+            Kernel::sierra(),
+            Kernel::two_row_sierra(),
+            Kernel::sierra_lite(),
+        ] {
+            // The share of black pixels follows the gray level.
+            for v in [32, 64, 128, 192, 224] {
+                let share = black_share(&gray(32, 32, v).dither(&kernel));
+                assert!(
+                    (share - (1. - v as f64 / 255.)).abs() < 0.03,
+                    "{v}: {share}"
+                );
+            }
+            assert_eq!(black_share(&gray(8, 8, 0).dither(&kernel)), 1.);
+            assert_eq!(black_share(&gray(8, 8, 255).dither(&kernel)), 0.);
+        }
+        // Half gray becomes a checkerboard with Floyd–Steinberg.
+        let checkers = gray(6, 6, 128).dither(&Kernel::floyd_steinberg());
+        for (x, y) in checkers.black_pixels() {
+            assert_eq!((x + y) % 2, 1);
+        }
+        assert_eq!(checkers.black_pixels().len(), 18);
+    }
+
+    #[test]
+    fn gray_levels() {
+        let options = DitherOptions::default();
+        assert_eq!(options.gray(BLACK), 0.);
+        assert!((options.gray(WHITE) - 1.).abs() < 1e-12);
+        let green = from_u8_rgb(0, 255, 0);
+        assert!((options.gray(green) - 0.587).abs() < 1e-12);
+        let rec709 = DitherOptions {
+            luma: Luma::Rec709,
+            ..options
+        };
+        assert!((rec709.gray(green) - 0.7152).abs() < 1e-12);
+        let blue = DitherOptions {
+            luma: Luma::Custom(0., 0., 1.),
+            ..options
+        };
+        assert!((blue.gray(from_u8_rgb(0, 0, 51)) - 0.2).abs() < 1e-12);
+
+        // Mid gray in sRGB is about a fifth of white's light.
+        let linear = DitherOptions {
+            linearize: true,
+            ..rec709
+        };
+        let mid = from_u8_rgb(128, 128, 128);
+        assert!((linear.gray(mid) - 0.2158).abs() < 1e-4);
+        assert!((linear.gray(WHITE) - 1.).abs() < 1e-12);
+        let image = gray(32, 32, 128);
+        let share = black_share(&image.dither_with(&Kernel::floyd_steinberg(), &linear));
+        assert!((share - 0.784).abs() < 0.03, "{share}");
+    }
+
+    #[test]
+    fn options() {
+        // Without diffusion, dithering is thresholding.
+        let plain = Kernel::new(&[], 1).unwrap();
+        let image = gray(3, 2, 100);
+        let at = |threshold| DitherOptions {
+            threshold,
+            ..DitherOptions::default()
+        };
+        assert_eq!(black_share(&image.dither_with(&plain, &at(0.3))), 0.);
+        assert_eq!(black_share(&image.dither_with(&plain, &at(0.4))), 1.);
+
+        // With error only going right, rows are independent, and serpentine scanning
+        // dithers odd rows as their mirror image would be dithered left to right.
+        let right = Kernel::new(&[(1, 0, 1)], 1).unwrap();
+        let mut image = Image::new(7, 2, 0, 0);
+        for x in 0..7u8 {
+            image.bytes[x as usize] = from_u8_rgb(40 * x, 40 * x, 40 * x);
+            image.bytes[7 + x as usize] = from_u8_rgb(30 + 30 * x, 30 + 30 * x, 30 + 30 * x);
+        }
+        let serpentine = DitherOptions {
+            serpentine: true,
+            ..DitherOptions::default()
+        };
+        let forward = image.dither(&right);
+        let both_ways = image.dither_with(&right, &serpentine);
+        let mirrored = image.flip_horizontal().dither(&right).flip_horizontal();
+        assert_eq!(both_ways.bytes[..7], forward.bytes[..7]);
+        assert_eq!(both_ways.bytes[7..], mirrored.bytes[7..]);
+        assert_ne!(forward.bytes[7..], mirrored.bytes[7..]);
+
+        // A clamp below the distance to black drops the isolated dots of dark areas.
+        let dark = gray(32, 32, 10);
+        let clamped = DitherOptions {
+            error_clamp: Some(0.2),
+            ..DitherOptions::default()
+        };
+        assert!(black_share(&dark.dither(&Kernel::floyd_steinberg())) < 1.);
+        assert_eq!(
+            black_share(&dark.dither_with(&Kernel::floyd_steinberg(), &clamped)),
+            1.
+        );
+    }
+
+    /// The cells of `map` with the `n` lowest thresholds.
+    fn lowest(map: &ThresholdMap, n: usize) -> Vec<(usize, usize)> {
+        let mut cells = (0..map.values().len()).collect::<Vec<_>>();
+        cells.sort_by(|a, b| map.values()[*a].total_cmp(&map.values()[*b]));
+        let mut ret = cells[..n]
+            .iter()
+            .map(|i| (i % map.width(), i / map.width()))
+            .collect::<Vec<_>>();
+        ret.sort();
+        ret
+    }
+
+    #[test]
+    fn threshold_maps() {
+        let bayer = ThresholdMap::bayer(2).unwrap();
+        assert_eq!(bayer.values(), [0.125, 0.625, 0.875, 0.375]);
+        // Every level of a dispersed-dot map spreads its cells over the quadrants.
+        let bayer = ThresholdMap::bayer(8).unwrap();
+        assert_eq!((bayer.width(), bayer.height()), (8, 8));
+        assert_eq!(lowest(&bayer, 4), [(0, 0), (0, 4), (4, 0), (4, 4)]);
+        for n in 1..=4 {
+            let mut quadrants = lowest(&bayer, 16 * n)
+                .into_iter()
+                .map(|(x, y)| (x / 4, y / 4))
+                .collect::<Vec<_>>();
+            quadrants.sort();
+            quadrants.dedup();
+            assert_eq!(quadrants.len(), 4);
+        }
+
+        // Clustered dots grow from the middle, and the diagonal ones from the corners too.
+        let clustered = ThresholdMap::clustered_dot(4).unwrap();
+        assert_eq!(lowest(&clustered, 4), [(1, 1), (1, 2), (2, 1), (2, 2)]);
+        let diagonal = ThresholdMap::diagonal_clustered_dot(4).unwrap();
+        assert_eq!(lowest(&diagonal, 4), [(1, 1), (1, 2), (2, 1), (2, 2)]);
+        assert_eq!(
+            lowest(&diagonal, 8)
+                .into_iter()
+                .filter(|(x, y)| (*x == 0 || *x == 3) && (*y == 0 || *y == 3))
+                .count(),
+            4
+        );
+
+        let map = ThresholdMap::bayer(16).unwrap();
+        assert_eq!(ThresholdMap::from_image(&map.to_image()), Some(map));
+    }
+
+    #[test]
+    fn ordered() {
+        let map = ThresholdMap::bayer(4).unwrap();
+        let options = DitherOptions::default();
+        for v in [0, 16, 64, 128, 200, 255] {
+            let dithered = gray(8, 8, v).dither_ordered(&map, &options);
+            let levels = map
+                .values()
+                .iter()
+                .filter(|t| v as f64 / 255. <= **t)
+                .count();
+            assert_eq!(dithered.black_pixels().len(), 4 * levels, "{v}");
+        }
+        let checkers = gray(4, 4, 128).dither_ordered(&map, &options);
+        for (x, y) in checkers.black_pixels() {
+            assert_eq!((x + y) % 2, 1);
+        }
+
+        // A pixel's result doesn't depend on its neighbors.
+        let mut image = gray(8, 8, 100);
+        let before = image.dither_ordered(&map, &options);
+        image.plot(3, 5);
+        let after = image.dither_ordered(&map, &options);
+        for (i, (a, b)) in before.bytes.iter().zip(&after.bytes).enumerate() {
+            assert!(a == b || i == 5 * 8 + 3);
+        }
+    }
+
+    #[test]
+    fn blue_noise() {
+        let map = ThresholdMap::blue_noise(16, 7).unwrap();
+        let mut values = map.values().to_vec();
+        values.sort_by(f64::total_cmp);
+        assert!(values
+            .iter()
+            .enumerate()
+            .all(|(r, v)| *v == (r as f64 + 0.5) / 256.));
+        assert_eq!(ThresholdMap::blue_noise(16, 7), Some(map.clone()));
+        assert_ne!(ThresholdMap::blue_noise(16, 8), Some(map.clone()));
+        assert_eq!(ThresholdMap::blue_noise(1, 7).unwrap().values(), [0.5]);
+
+        // The first and last cells to turn on are kept apart, also across the edges of the tile.
+        let distance = |(x1, y1): (usize, usize), (x2, y2): (usize, usize)| {
+            let (dx, dy) = (x1.abs_diff(x2), y1.abs_diff(y2));
+            dx.min(16 - dx).max(dy.min(16 - dy))
+        };
+        let inverted =
+            ThresholdMap::new(16, 16, map.values().iter().map(|v| 1. - v).collect()).unwrap();
+        for level in [lowest(&map, 16), lowest(&inverted, 16)] {
+            let closest = level
+                .iter()
+                .flat_map(|a| {
+                    level
+                        .iter()
+                        .filter(move |b| a != *b)
+                        .map(move |b| distance(*a, *b))
+                })
+                .min()
+                .unwrap();
+            assert!(closest >= 2, "{closest}");
+        }
+    }
+
+    #[test]
+    fn riemersma() {
+        let options = DitherOptions::default();
+        assert_eq!(black_share(&gray(9, 7, 0).dither_riemersma(&options)), 1.);
+        assert_eq!(black_share(&gray(9, 7, 255).dither_riemersma(&options)), 0.);
+        for v in [64, 128, 192] {
+            let share = black_share(&gray(32, 32, v).dither_riemersma(&options));
+            assert!(
+                (share - (1. - v as f64 / 255.)).abs() < 0.05,
+                "{v}: {share}"
+            );
+        }
+        let mut image = gray(5, 3, 128);
+        image.hotspot = Some((4, 2));
+        let dithered = image.dither_riemersma(&options);
+        assert_eq!((dithered.width, dithered.height), (5, 3));
+        assert_eq!(dithered.hotspot, Some((4, 2)));
+        assert!(Image::new(0, 4, 0, 0)
+            .dither_riemersma(&options)
+            .bytes
+            .is_empty());
+    }
+}
@@ -0,0 +1,207 @@
+//! Shrinking images without losing thin strokes. Every destination pixel covers a rectangle
+//! of the source, which may cut through source pixels, so each source pixel counts by the
+//! area it shares with that rectangle.
+
+use std::f64::consts::PI;
+
+use crate::{from_u32_rgb, from_u8_rgb, Image, BLACK, WHITE};
+
+/// How `Image::downscale` combines the source pixels under a destination pixel.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Downscale {
+    /// The average of their colors, weighted by area. Produces grays from a black and white
+    /// image.
+    Box,
+    /// `BLACK` if any of them is, otherwise `WHITE`. Strokes survive but get bolder.
+    AnyBlack,
+    /// `BLACK` if at least half of the area is, otherwise `WHITE`.
+    Majority,
+    /// Lanczos filtering with three lobes, which keeps grayscale images sharper than `Box`.
+    Lanczos,
+}
+
+/// For each destination pixel along an axis, the source pixels it overlaps and by how much.
+fn spans(src: usize, dst: usize) -> Vec<Vec<(usize, f64)>> {
+    let ratio = src as f64 / dst as f64;
+    (0..dst)
+        .map(|i| {
+            let (start, end) = (i as f64 * ratio, (i + 1) as f64 * ratio);
+            (start.floor() as usize..(end.ceil() as usize).min(src))
+                .map(|s| (s, end.min(s as f64 + 1.) - start.max(s as f64)))
+                .filter(|(_, w)| *w > 1e-9)
+                .collect()
+        })
+        .collect()
+}
+
+fn lanczos(x: f64) -> f64 {
+    const A: f64 = 3.;
+    if x == 0. {
+        1.
+    } else if x.abs() >= A {
+        0.
+    } else {
+        let px = PI * x;
+        A * px.sin() * (px / A).sin() / (px * px)
+    }
+}
+
+/// For each destination pixel along an axis, the Lanczos weights of the source pixels, which
+/// sum to one.
+fn lanczos_weights(src: usize, dst: usize) -> Vec<Vec<(usize, f64)>> {
+    let ratio = src as f64 / dst as f64;
+    // When shrinking, the kernel is stretched to cover every source pixel.
+    let scale = ratio.max(1.);
+    (0..dst)
+        .map(|i| {
+            let center = (i as f64 + 0.5) * ratio - 0.5;
+            let reach = 3. * scale;
+            let first = (center - reach).floor().max(0.) as usize;
+            let last = ((center + reach).ceil() as usize).min(src - 1);
+            let weights = (first..=last)
+                .map(|s| (s, lanczos((s as f64 - center) / scale)))
+                .collect::<Vec<_>>();
+            let total = weights.iter().map(|(_, w)| w).sum::<f64>();
+            weights.into_iter().map(|(s, w)| (s, w / total)).collect()
+        })
+        .collect()
+}
+
+impl Image {
+    /// Shrinks (or stretches) the image to `width` × `height`, combining source pixels
+    /// according to `mode`. Unlike `resize`, which samples one source pixel per destination
+    /// pixel, every source pixel contributes.
+    pub fn downscale(&self, width: usize, height: usize, mode: Downscale) -> Image {
+        let mut ret = Image::new(width, height, self.x_offset, self.y_offset);
+        if width == 0 || height == 0 || self.width == 0 || self.height == 0 {
+            return ret;
+        }
+        let (columns, rows) = match mode {
+            Downscale::Lanczos => (
+                lanczos_weights(self.width, width),
+                lanczos_weights(self.height, height),
+            ),
+            _ => (spans(self.width, width), spans(self.height, height)),
+        };
+        for (y, row) in rows.iter().enumerate() {
+            for (x, column) in columns.iter().enumerate() {
+                let (mut sum, mut black, mut total) = ([0.; 3], 0., 0.);
+                for (sy, wy) in row {
+                    for (sx, wx) in column {
+                        let p = self.bytes[sy * self.width + sx];
+                        let w = wx * wy;
+                        let (r, g, b) = from_u32_rgb(p);
+                        sum[0] += w * r as f64;
+                        sum[1] += w * g as f64;
+                        sum[2] += w * b as f64;
+                        if p == BLACK {
+                            black += w;
+                        }
+                        total += w;
+                    }
+                }
+                let channel = |v: f64| (v / total).round().clamp(0., 255.) as u8;
+                ret.bytes[y * width + x] = match mode {
+                    Downscale::Box | Downscale::Lanczos => {
+                        from_u8_rgb(channel(sum[0]), channel(sum[1]), channel(sum[2]))
+                    }
+                    Downscale::AnyBlack if black > 0. => BLACK,
+                    Downscale::Majority if black >= total / 2. => BLACK,
+                    Downscale::AnyBlack | Downscale::Majority => WHITE,
+                };
+            }
+        }
+        ret.hotspot = self
+            .hotspot
+            .map(|(x, y)| (x * width / self.width, y * height / self.height));
+        ret
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gray(v: u8) -> u32 {
+        from_u8_rgb(v, v, v)
+    }
+
+    #[test]
+    fn weights() {
+        assert_eq!(
+            spans(3, 2),
+            [vec![(0, 1.), (1, 0.5)], vec![(1, 0.5), (2, 1.)]]
+        );
+        assert_eq!(spans(2, 4)[1], [(0, 0.5)]);
+        for (src, dst) in [(10, 3), (3, 10), (7, 7), (1, 5)] {
+            for w in lanczos_weights(src, dst) {
+                assert!((w.iter().map(|(_, w)| w).sum::<f64>() - 1.).abs() < 1e-9);
+            }
+        }
+    }
+
+    #[test]
+    fn modes() {
+        // A one pixel wide vertical line in a 3×3 block.
+        let mut line = Image::new(3, 3, 0, 0);
+        for y in 0..3 {
+            line.plot(1, y);
+        }
+        let one = |mode| line.downscale(1, 1, mode).bytes[0];
+        assert_eq!(one(Downscale::Box), gray(170));
+        assert_eq!(one(Downscale::AnyBlack), BLACK);
+        assert_eq!(one(Downscale::Majority), WHITE);
+
+        // Source pixels cut in half count for half.
+        let mut row = Image::new(3, 1, 0, 0);
+        row.plot(0, 0);
+        assert_eq!(row.downscale(2, 1, Downscale::Box).bytes, [gray(85), WHITE]);
+        assert_eq!(
+            row.downscale(2, 1, Downscale::Majority).bytes,
+            [BLACK, WHITE]
+        );
+
+        let mut checkers = Image::new(4, 4, 0, 0);
+        for (x, y) in [
+            (0, 0),
+            (1, 1),
+            (2, 2),
+            (3, 3),
+            (0, 2),
+            (2, 0),
+            (1, 3),
+            (3, 1),
+        ] {
+            checkers.plot(x, y);
+        }
+        let shrunk = checkers.downscale(2, 2, Downscale::Box);
+        assert!(shrunk.bytes.iter().all(|c| *c == gray(128)));
+        let majority = checkers.downscale(2, 2, Downscale::Majority);
+        assert!(majority.bytes.iter().all(|c| *c == BLACK));
+    }
+
+    #[test]
+    fn lanczos() {
+        let mut image = Image::new(6, 5, 0, 0);
+        for p in [(0, 0), (2, 1), (5, 4), (3, 3)] {
+            image.plot(p.0, p.1);
+        }
+        assert_eq!(image.downscale(6, 5, Downscale::Lanczos).bytes, image.bytes);
+        let flat = Image {
+            bytes: vec![gray(90); 64],
+            ..Image::new(8, 8, 0, 0)
+        };
+        let shrunk = flat.downscale(3, 3, Downscale::Lanczos);
+        assert!(shrunk.bytes.iter().all(|c| *c == gray(90)));
+    }
+
+    #[test]
+    fn sizes() {
+        let mut image = Image::new(8, 6, 0, 0);
+        image.hotspot = Some((5, 3));
+        let shrunk = image.downscale(4, 3, Downscale::AnyBlack);
+        assert_eq!((shrunk.width, shrunk.height), (4, 3));
+        assert_eq!(shrunk.hotspot, Some((2, 1)));
+        assert!(image.downscale(0, 3, Downscale::Box).bytes.is_empty());
+    }
+}
@@ -0,0 +1,209 @@
+use crate::{Image, Point};
+
+/// Decides which regions of a self-intersecting or multi-contour polygon are inside.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FillRule {
+    /// Inside if a ray from the point crosses the outline an odd number of times.
+    EvenOdd,
+    /// Inside if the outline winds around the point a nonzero number of times. Holes must then
+    /// run in the opposite direction to their outer contour.
+    NonZero,
+}
+
+struct Edge {
+    y_min: i64,
+    y_max: i64,
+    /// The upper end of the edge and how far the lower one is from it.
+    top: Point,
+    dx: i64,
+    dy: i64,
+    /// x where the edge crosses the current scanline.
+    x: f64,
+    winding: i32,
+}
+
+/// x where the edge from `top` to `top + (dx, dy)` crosses scanline `y`. Computed from scratch
+/// every time, so that errors don't accumulate and `point_in_polygon` agrees exactly.
+pub(crate) fn edge_crossing(top: Point, dx: i64, dy: i64, y: i64) -> f64 {
+    top.0 as f64 + (dx * (y - top.1)) as f64 / dy as f64
+}
+
+impl Image {
+    pub fn fill_polygon(&mut self, polygon: &[Point], rule: FillRule) {
+        self.fill_contours(&[polygon], rule, None)
+    }
+
+    /// Fills the area enclosed by one or more closed `contours`, such as an outline and its
+    /// holes. Pixels are inside when their center is, with the right and bottom edges of the
+    /// polygon left out, so that adjacent polygons don't overlap.
+    pub fn fill_contours(&mut self, contours: &[&[Point]], rule: FillRule, color: Option<u32>) {
+        let clip = self.clip_rect();
+        let mut edges = vec![];
+        for contour in contours {
+            for (i, &(x0, y0)) in contour.iter().enumerate() {
+                let (x1, y1) = contour[(i + 1) % contour.len()];
+                if y0 == y1 {
+                    continue;
+                }
+                let (winding, (xa, ya), (xb, yb)) = if y0 < y1 {
+                    (1, (x0, y0), (x1, y1))
+                } else {
+                    (-1, (x1, y1), (x0, y0))
+                };
+                // Scanlines in ya..yb cross this edge, clipped to the top of the clip rect.
+                edges.push(Edge {
+                    y_min: ya.max(clip.y),
+                    y_max: yb,
+                    top: (xa, ya),
+                    dx: xb - xa,
+                    dy: yb - ya,
+                    x: 0.,
+                    winding,
+                });
+            }
+        }
+        edges.sort_unstable_by_key(|e| e.y_min);
+
+        let y_end = edges
+            .iter()
+            .map(|e| e.y_max)
+            .max()
+            .unwrap_or(0)
+            .min(clip.y + clip.height as i64);
+        let mut pending = edges.into_iter().peekable();
+        let mut active: Vec<Edge> = vec![];
+        let mut y = match pending.peek() {
+            Some(e) => e.y_min,
+            None => return,
+        };
+        while y < y_end {
+            while let Some(e) = pending.next_if(|e| e.y_min == y) {
+                active.push(e);
+            }
+            active.retain(|e| e.y_max > y);
+            for e in active.iter_mut() {
+                e.x = edge_crossing(e.top, e.dx, e.dy, y);
+            }
+            active.sort_unstable_by(|a, b| a.x.total_cmp(&b.x));
+
+            let mut winding = 0;
+            for pair in active.windows(2) {
+                winding += pair[0].winding;
+                let inside = match rule {
+                    FillRule::EvenOdd => winding % 2 != 0,
+                    FillRule::NonZero => winding != 0,
+                };
+                if !inside {
+                    continue;
+                }
+                let x_start = (pair[0].x.ceil() as i64).max(clip.x);
+                let x_end = (pair[1].x.ceil() as i64).min(clip.x + clip.width as i64);
+                for x in x_start..x_end {
+                    self.plot_color(x, y, color);
+                }
+            }
+            y += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{point_in_polygon, Rect, BLACK, WHITE};
+
+    fn filled(polygon: &[Point], rule: FillRule) -> Image {
+        let mut image = Image::new(32, 32, 0, 0);
+        image.fill_polygon(polygon, rule);
+        image
+    }
+
+    #[test]
+    fn agrees_with_point_in_polygon() {
+        let polygons: [&[Point]; 4] = [
+            // A pentagram, whose middle is wound twice.
+            &[(16, 2), (25, 29), (2, 12), (30, 12), (7, 29)],
+            // Concave, with a slanted notch.
+            &[(1, 1), (30, 3), (12, 15), (29, 28), (3, 30)],
+            // Self-intersecting bow tie.
+            &[(2, 2), (29, 27), (29, 4), (3, 28)],
+            // An outline looping twice around the same square.
+            &[
+                (4, 4),
+                (20, 4),
+                (20, 20),
+                (4, 20),
+                (4, 4),
+                (20, 4),
+                (20, 20),
+                (4, 20),
+            ],
+        ];
+        for polygon in polygons {
+            for rule in [FillRule::EvenOdd, FillRule::NonZero] {
+                let image = filled(polygon, rule);
+                for y in 0..32 {
+                    for x in 0..32 {
+                        assert_eq!(
+                            image.get(x, y) == Some(BLACK),
+                            point_in_polygon((x, y), polygon, rule),
+                            "{:?} {:?} at {:?}",
+                            polygon,
+                            rule,
+                            (x, y)
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn rules() {
+        let star = [(16, 2), (25, 29), (2, 12), (30, 12), (7, 29)];
+        assert_eq!(filled(&star, FillRule::EvenOdd).get(16, 17), Some(WHITE));
+        assert_eq!(filled(&star, FillRule::NonZero).get(16, 17), Some(BLACK));
+    }
+
+    #[test]
+    fn adjacent_squares_share_no_pixels() {
+        let mut image = Image::new(10, 10, 0, 0);
+        image.fill_polygon(&[(0, 0), (4, 0), (4, 4), (0, 4)], FillRule::NonZero);
+        assert_eq!(image.black_pixels().len(), 16);
+        image.fill_polygon(&[(4, 0), (8, 0), (8, 4), (4, 4)], FillRule::NonZero);
+        assert_eq!(image.black_pixels().len(), 32);
+    }
+
+    #[test]
+    fn holes() {
+        let outer: &[Point] = &[(2, 2), (28, 2), (28, 28), (2, 28)];
+        let hole: &[Point] = &[(10, 10), (10, 20), (20, 20), (20, 10)];
+        let same_way: &[Point] = &[(10, 10), (20, 10), (20, 20), (10, 20)];
+        let fill = |contours: &[&[Point]], rule| {
+            let mut image = Image::new(32, 32, 0, 0);
+            image.fill_contours(contours, rule, None);
+            image.get(15, 15)
+        };
+        assert_eq!(fill(&[outer, hole], FillRule::NonZero), Some(WHITE));
+        assert_eq!(fill(&[outer, same_way], FillRule::NonZero), Some(BLACK));
+        assert_eq!(fill(&[outer, same_way], FillRule::EvenOdd), Some(WHITE));
+    }
+
+    #[test]
+    fn clip() {
+        let mut image = Image::new(32, 32, 0, 0);
+        image.clip = Some(Rect {
+            x: 5,
+            y: 6,
+            width: 10,
+            height: 4,
+        });
+        image.fill_polygon(
+            &[(-100, -100), (100, -100), (100, 100), (-100, 100)],
+            FillRule::EvenOdd,
+        );
+        let pixels = image.black_pixels();
+        assert_eq!(pixels.len(), 40);
+        assert!(pixels.iter().all(|p| image.is_visible(*p)));
+    }
+}
@@ -0,0 +1,342 @@
+use crate::fill::edge_crossing;
+use crate::vector;
+use crate::{FillRule, Image, Line, Point};
+
+pub fn distance_between_two_points((x_k, y_k): Point, (x_l, y_l): Point) -> f64 {
+    let xlk = x_l - x_k;
+    let ylk = y_l - y_k;
+    f64::sqrt((xlk * xlk + ylk * ylk) as f64)
+}
+
+/// The line `ax + by + c = 0` through both points, or `None` if they're the same point.
+pub fn find_line((xa, ya): Point, (xb, yb): Point) -> Option<Line> {
+    if (xa, ya) == (xb, yb) {
+        return None;
+    }
+    Some((ya - yb, xb - xa, xa * yb - xb * ya))
+}
+
+/// `None` if the lines are parallel or either of them is degenerate.
+pub fn find_intersection((a1, b1, c1): Line, (a2, b2, c2): Line) -> Option<Point> {
+    let denom = a1 * b2 - a2 * b1;
+    if denom == 0 {
+        return None;
+    }
+    Some(((b1 * c2 - b2 * c1) / denom, (a2 * c1 - a1 * c2) / denom))
+}
+
+/// The line of points equidistant from `point_a` and `point_b`. Its coefficients are exact,
+/// since the midpoint isn't rounded.
+pub fn perp_bisector((xa, ya): Point, (xb, yb): Point) -> Option<Line> {
+    if (xa, ya) == (xb, yb) {
+        return None;
+    }
+    Some((
+        2 * (xb - xa),
+        2 * (yb - ya),
+        xa * xa + ya * ya - xb * xb - yb * yb,
+    ))
+}
+
+/// The angle between the normals of two lines, in radians.
+pub fn find_angle((a1, b1, _c1): Line, (a2, b2, _c2): Line) -> Option<f64> {
+    let nom = (a1 * a2 + b1 * b2) as f64;
+    let denom = ((a1 * a1 + b1 * b1) * (a2 * a2 + b2 * b2)) as f64;
+    if denom == 0. {
+        return None;
+    }
+    Some(f64::acos((nom / f64::sqrt(denom)).clamp(-1., 1.)))
+}
+
+/// Signed distance, positive on the side the normal `(a, b)` points to.
+pub fn distance_line_to_point((x, y): Point, (a, b, c): Line) -> Option<f64> {
+    let d = f64::sqrt((a * a + b * b) as f64);
+    if d == 0.0 {
+        return None;
+    }
+    Some((a * x + b * y + c) as f64 / d)
+}
+
+pub fn perpendicular((a, b, _c): Line, p: Point) -> Line {
+    (b, -a, a * p.1 - b * p.0)
+}
+
+/// The foot of the perpendicular from `p` to the line.
+pub fn point_perpendicular((a, b, c): Line, p: Point) -> Option<Point> {
+    let d = (a * a + b * b) as f64;
+    if d == 0. {
+        return None;
+    }
+    let cp = a * p.1 - b * p.0;
+    Some((
+        ((-a * c - b * cp) as f64 / d) as i64,
+        ((a * cp - b * c) as f64 / d) as i64,
+    ))
+}
+
+/// The reflection of `point` across the line.
+pub fn find_mirror((x, y): Point, (a, b, c): Line) -> Option<Point> {
+    let d = (a * a + b * b) as f64;
+    if d == 0. {
+        return None;
+    }
+    let t = 2. * (a * x + b * y + c) as f64 / d;
+    Some((
+        (x as f64 - t * a as f64).round() as i64,
+        (y as f64 - t * b as f64).round() as i64,
+    ))
+}
+
+/// Andrew's monotone chain, clockwise on screen where y grows downwards, without collinear
+/// points.
+pub fn convex_hull(points: &[Point]) -> Vec<Point> {
+    let mut points = points.to_vec();
+    points.sort_unstable();
+    points.dedup();
+    if points.len() < 3 {
+        return points;
+    }
+    let turn = |o: Point, a: Point, b: Point| (a.0 - o.0) * (b.1 - o.1) - (a.1 - o.1) * (b.0 - o.0);
+    let mut hull: Vec<Point> = Vec::with_capacity(2 * points.len());
+    for pass in [points.clone(), points.into_iter().rev().collect()] {
+        let start = hull.len();
+        for p in pass {
+            while hull.len() >= start + 2
+                && turn(hull[hull.len() - 2], hull[hull.len() - 1], p) <= 0
+            {
+                hull.pop();
+            }
+            hull.push(p);
+        }
+        hull.pop();
+    }
+    hull
+}
+
+/// Twice the shoelace sum, which is exact for integer vertices. Positive when the polygon runs
+/// clockwise on screen, where y grows downwards.
+fn doubled_signed_area(polygon: &[Point]) -> i64 {
+    polygon
+        .iter()
+        .zip(polygon.iter().cycle().skip(1))
+        .map(|(a, b)| a.0 * b.1 - b.0 * a.1)
+        .sum()
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Winding {
+    Clockwise,
+    CounterClockwise,
+}
+
+/// The orientation of a polygon as seen on screen, `None` if it has no area.
+pub fn polygon_winding(polygon: &[Point]) -> Option<Winding> {
+    match doubled_signed_area(polygon) {
+        0 => None,
+        a if a > 0 => Some(Winding::Clockwise),
+        _ => Some(Winding::CounterClockwise),
+    }
+}
+
+/// The area enclosed by a simple polygon.
+pub fn polygon_area(polygon: &[Point]) -> f64 {
+    doubled_signed_area(polygon).abs() as f64 / 2.
+}
+
+pub fn polygon_perimeter(polygon: &[Point]) -> f64 {
+    polygon
+        .iter()
+        .zip(polygon.iter().cycle().skip(1))
+        .map(|(a, b)| distance_between_two_points(*a, *b))
+        .sum()
+}
+
+/// The center of mass of a simple polygon's area, `None` if it has no area.
+pub fn polygon_centroid(polygon: &[Point]) -> Option<vector::Point<f64>> {
+    let area2 = doubled_signed_area(polygon);
+    if area2 == 0 {
+        return None;
+    }
+    let (cx, cy) =
+        polygon
+            .iter()
+            .zip(polygon.iter().cycle().skip(1))
+            .fold((0, 0), |(cx, cy), (a, b)| {
+                let cross = a.0 * b.1 - b.0 * a.1;
+                (cx + (a.0 + b.0) * cross, cy + (a.1 + b.1) * cross)
+            });
+    let area6 = 3. * area2 as f64;
+    Some(vector::Point::new(cx as f64 / area6, cy as f64 / area6))
+}
+
+/// Whether `p` is inside the polygon under `rule`. Points on the boundary are decided the same
+/// way as by `Image::fill_polygon`, which fills exactly the pixels this returns `true` for.
+pub fn point_in_polygon((x, y): Point, polygon: &[Point], rule: FillRule) -> bool {
+    let mut winding = 0;
+    for (a, b) in polygon.iter().zip(polygon.iter().cycle().skip(1)) {
+        let (dir, lo, hi) = match a.1.cmp(&b.1) {
+            std::cmp::Ordering::Less => (1, a, b),
+            std::cmp::Ordering::Greater => (-1, b, a),
+            std::cmp::Ordering::Equal => continue,
+        };
+        if y < lo.1 || y >= hi.1 {
+            continue;
+        }
+        if x as f64 >= edge_crossing(*lo, hi.0 - lo.0, hi.1 - lo.1, y) {
+            winding += dir;
+        }
+    }
+    match rule {
+        FillRule::EvenOdd => winding % 2 != 0,
+        FillRule::NonZero => winding != 0,
+    }
+}
+
+impl Image {
+    /// The convex hull of every `BLACK` pixel.
+    pub fn convex_hull(&self) -> Vec<Point> {
+        convex_hull(&self.black_pixels())
+    }
+
+    /// Plots the part of an implicit line that crosses the clip rect.
+    pub fn plot_line(&mut self, (a, b, c): Line, wd: f64) {
+        let clip = self.clip_rect();
+        let (x0, y0) = (clip.x, clip.y);
+        let (x1, y1) = (x0 + clip.width as i64 - 1, y0 + clip.height as i64 - 1);
+        let solve = |k: i64, l: i64, v: i64| (-(c + l * v) as f64 / k as f64).round() as i64;
+        if a == 0 && b == 0 {
+            return;
+        }
+        // The ends are on the borders the line crosses most steeply, which leaves at most the
+        // width of the clip rect to clip away.
+        if a.abs() >= b.abs() {
+            self.plot_line_width((solve(a, b, y0), y0), (solve(a, b, y1), y1), wd);
+        } else {
+            self.plot_line_width((x0, solve(b, a, x0)), (x1, solve(b, a, x1)), wd);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn on_line((a, b, c): Line, (x, y): Point) -> bool {
+        a * x + b * y + c == 0
+    }
+
+    #[test]
+    fn lines() {
+        assert_eq!(distance_between_two_points((1, 2), (4, 6)), 5.);
+        let line = find_line((2, 3), (8, 7)).unwrap();
+        assert!(on_line(line, (2, 3)) && on_line(line, (8, 7)));
+        assert!(find_line((2, 3), (2, 3)).is_none());
+
+        let (diagonal, anti) = (find_line((0, 0), (1, 1)).unwrap(), (1, 1, -10));
+        assert_eq!(find_intersection(diagonal, anti), Some((5, 5)));
+        assert_eq!(find_intersection(diagonal, (2, -2, 7)), None);
+        let angle = find_angle(diagonal, anti).unwrap();
+        assert!((angle - std::f64::consts::FRAC_PI_2).abs() < 1e-12);
+    }
+
+    #[test]
+    fn perpendiculars() {
+        let bisector = perp_bisector((2, 3), (8, 7)).unwrap();
+        assert!(on_line(bisector, (5, 5)));
+        let (da, db) = (
+            distance_line_to_point((2, 3), bisector).unwrap(),
+            distance_line_to_point((8, 7), bisector).unwrap(),
+        );
+        assert!((da + db).abs() < 1e-12 && da != 0.);
+        assert!(perp_bisector((1, 1), (1, 1)).is_none());
+
+        let x_axis = (0, 1, 0);
+        let perpendicular = perpendicular(x_axis, (7, 5));
+        assert!(on_line(perpendicular, (7, 5)));
+        assert_eq!(perpendicular.0 * x_axis.0 + perpendicular.1 * x_axis.1, 0);
+        assert_eq!(point_perpendicular(x_axis, (7, 5)), Some((7, 0)));
+        assert_eq!(distance_line_to_point((7, -5), x_axis), Some(-5.));
+        assert_eq!(find_mirror((3, 8), (1, -1, 0)), Some((8, 3)));
+        assert_eq!(find_mirror((3, 8), (0, 0, 1)), None);
+    }
+
+    #[test]
+    fn plot_implicit_line() {
+        let mut image = Image::new(10, 8, 0, 0);
+        image.plot_line((0, 1, -3), 1.);
+        assert_eq!(
+            image.black_pixels(),
+            (0..10).map(|x| (x, 3)).collect::<Vec<_>>()
+        );
+        let mut image = Image::new(10, 8, 0, 0);
+        image.plot_line((1, -1, 0), 1.);
+        assert_eq!(
+            image.black_pixels(),
+            (0..8).map(|x| (x, x)).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn hull() {
+        let points = [
+            (0, 0),
+            (4, 0),
+            (2, 2),
+            (4, 4),
+            (0, 4),
+            (2, 0),
+            (1, 3),
+            (4, 2),
+        ];
+        // Clockwise on screen, without the collinear (2, 0) and (4, 2).
+        let hull = convex_hull(&points);
+        assert_eq!(hull, [(0, 0), (4, 0), (4, 4), (0, 4)]);
+        assert_eq!(polygon_winding(&hull), Some(Winding::Clockwise));
+        assert_eq!(convex_hull(&[(1, 1), (1, 1), (2, 2)]), [(1, 1), (2, 2)]);
+
+        let mut image = Image::new(20, 20, 0, 0);
+        let blob = [(3, 9), (7, 2), (8, 8), (15, 11), (9, 17), (6, 12), (10, 6)];
+        for (x, y) in blob {
+            image.plot(x, y);
+        }
+        let hull = image.convex_hull();
+        assert_eq!(hull, convex_hull(&blob));
+        for (a, b) in hull.iter().zip(hull.iter().cycle().skip(1)) {
+            for p in blob {
+                assert!((b.0 - a.0) * (p.1 - a.1) - (b.1 - a.1) * (p.0 - a.0) >= 0);
+            }
+        }
+    }
+
+    #[test]
+    fn measurements() {
+        let square = [(0, 0), (4, 0), (4, 4), (0, 4)];
+        let reversed = square.iter().rev().copied().collect::<Vec<_>>();
+        assert_eq!(polygon_area(&square), 16.);
+        assert_eq!(polygon_area(&reversed), 16.);
+        assert_eq!(polygon_winding(&reversed), Some(Winding::CounterClockwise));
+        assert_eq!(polygon_winding(&[(0, 0), (1, 1), (2, 2)]), None);
+        assert_eq!(polygon_perimeter(&square), 16.);
+        assert_eq!(
+            polygon_centroid(&reversed),
+            Some(vector::Point::new(2., 2.))
+        );
+        // An L shape's centroid is pulled into its long arm.
+        let l = [(0, 0), (2, 0), (2, 4), (6, 4), (6, 6), (0, 6)];
+        assert_eq!(polygon_area(&l), 20.);
+        let c = polygon_centroid(&l).unwrap();
+        assert!((c.x - 2.2).abs() < 1e-12 && (c.y - 3.8).abs() < 1e-12);
+        assert_eq!(polygon_centroid(&[(0, 0), (1, 1), (2, 2)]), None);
+    }
+
+    #[test]
+    fn containment() {
+        let square = [(0, 0), (4, 0), (4, 4), (0, 4)];
+        // The top and left edges are inside, the bottom and right ones outside.
+        assert!(point_in_polygon((0, 0), &square, FillRule::EvenOdd));
+        assert!(point_in_polygon((3, 3), &square, FillRule::EvenOdd));
+        assert!(!point_in_polygon((4, 2), &square, FillRule::EvenOdd));
+        assert!(!point_in_polygon((2, 4), &square, FillRule::EvenOdd));
+        assert!(!point_in_polygon((-1, 2), &square, FillRule::NonZero));
+    }
+}
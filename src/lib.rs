@@ -1,8 +1,50 @@
-use regex::Regex;
-use std::collections::VecDeque;
-use std::fs::File;
-use std::io::prelude::*;
+use std::collections::{HashMap, VecDeque};
 use std::process::Command;
+
+pub mod antialias;
+pub mod bdf;
+pub mod bitmap;
+pub mod bounds;
+pub mod clip;
+pub mod curve;
+pub mod dither;
+pub mod downscale;
+pub mod fill;
+pub mod geometry;
+pub mod netpbm;
+pub mod psf;
+mod rng;
+pub mod rotate;
+pub mod smooth;
+pub mod stroke;
+pub mod text;
+pub mod transform;
+pub mod upscale;
+pub mod vector;
+pub mod xbm;
+
+pub use antialias::Composite;
+pub use bitmap::Bitmap;
+pub use bounds::{Circle, OrientedRect, Rect};
+pub use clip::{
+    clip_line_cohen_sutherland, clip_line_liang_barsky, clip_polygon, clip_polygon_convex,
+};
+pub use curve::{Hilbert, ZOrder};
+pub use dither::{DitherOptions, Kernel, Luma, ThresholdMap};
+pub use downscale::Downscale;
+pub use fill::FillRule;
+pub use geometry::{
+    convex_hull, distance_between_two_points, distance_line_to_point, find_angle,
+    find_intersection, find_line, find_mirror, perp_bisector, perpendicular, point_in_polygon,
+    point_perpendicular, polygon_area, polygon_centroid, polygon_perimeter, polygon_winding,
+    Winding,
+};
+pub use smooth::{RuleTile, SmoothingRule};
+pub use stroke::{Cap, Join, Stroke};
+pub use text::{Alignment, TextBox, TextLine};
+pub use transform::{Affine, Sampling};
+pub use xbm::{Xbm, XbmError, XbmErrorKind};
+
 pub type Point = (i64, i64);
 pub type Line = (i64, i64, i64);
 
@@ -29,6 +71,9 @@ pub struct Image {
     pub height: usize,
     pub x_offset: usize,
     pub y_offset: usize,
+    pub hotspot: Option<(usize, usize)>,
+    /// Restricts drawing to these pixels when set. Reading with `get` isn't affected.
+    pub clip: Option<Rect>,
 }
 
 impl Image {
@@ -39,6 +84,8 @@ impl Image {
             height,
             x_offset,
             y_offset,
+            hotspot: None,
+            clip: None,
         }
     }
 
@@ -72,45 +119,8 @@ impl Image {
             height,
             x_offset,
             y_offset,
-        })
-    }
-
-    pub fn from_xbm(
-        path: &str,
-        x_offset: usize,
-        y_offset: usize,
-    ) -> Result<Self, Box<dyn ::std::error::Error>> {
-        let mut file = File::open(path)?;
-        let mut s = String::new();
-        file.read_to_string(&mut s)?;
-        let re = Regex::new(
-            r"(?imx)
-  ^\s*\x23\s*define\s+(?P<i>.+?)_width\s+(?P<w>\d\d*)$
-  \s*
-  ^\s*\x23\s*define\s+.+?_height\s+(?P<h>\d\d*)$
-  \s*
-  ^\s*static(\s+unsigned){0,1}\s+char\s+.+?_bits..\s*=\s*\{(?P<b>[^}]+)\};
-  ",
-        )
-        .unwrap();
-        let caps = re
-            .captures(&s)
-            .ok_or("Could not open xbm file, regex doesn't match :(")?;
-        let width = caps.name("w").unwrap().as_str().parse::<usize>()?;
-        let height = caps.name("h").unwrap().as_str().parse::<usize>()?;
-        let bits = caps
-            .name("b")
-            .unwrap()
-            .as_str()
-            .split(',')
-            .map(|h| u8::from_str_radix(&h.trim()["0x".len()..], 16))
-            .collect::<Result<Vec<u8>, _>>()?;
-        Ok(Image {
-            bytes: bits_to_bytes(&bits, width),
-            width,
-            height,
-            x_offset,
-            y_offset,
+            hotspot: None,
+            clip: None,
         })
     }
 
@@ -165,8 +175,7 @@ impl Image {
     }
 
     pub fn plot(&mut self, x: i64, y: i64) {
-        if x < 0 || y < 0 || y >= (self.height as i64) || x >= (self.width as i64) {
-            //eprintln!("invalid plot() coors: ({}, {})", x, y);
+        if !self.is_visible((x, y)) {
             return;
         }
         let (x, y): (usize, usize) = (x as _, y as _);
@@ -174,8 +183,7 @@ impl Image {
     }
 
     pub fn plot_color(&mut self, x: i64, y: i64, color: Option<u32>) {
-        if x < 0 || y < 0 || y >= (self.height as i64) || x >= (self.width as i64) {
-            //eprintln!("invalid plot() coors: ({}, {})", x, y);
+        if !self.is_visible((x, y)) {
             return;
         }
         let (x, y): (usize, usize) = (x as _, y as _);
@@ -190,8 +198,8 @@ impl Image {
         Some(self.bytes[y * self.width + x])
     }
 
-    pub fn plot_circle(&mut self, center: Point, r: i64, _wd: f64) {
-        self.plot_ellipse(center, (r, r), [true, true, true, true], _wd)
+    pub fn plot_circle(&mut self, center: Point, r: i64, wd: f64) {
+        self.plot_ellipse(center, (r, r), [true, true, true, true], wd)
     }
 
     pub fn plot_square(&mut self, center: Point, r: i64, wd: f64) {
@@ -221,8 +229,12 @@ impl Image {
         (xm, ym): (i64, i64),
         (a, b): (i64, i64),
         quadrants: [bool; 4],
-        _wd: f64,
+        wd: f64,
     ) {
+        if wd > 1. {
+            self.stroke_ellipse((xm, ym), (a as f64, b as f64), quadrants, &Stroke::new(wd));
+            return;
+        }
         let mut x = -a;
         let mut y = 0;
         let mut e2 = b;
@@ -278,116 +290,38 @@ impl Image {
         wd: f64,
         color: Option<u32>,
     ) {
-        /* Bresenham's line algorithm */
-        let mut d;
-        let mut x: i64;
-        let mut y: i64;
-        let ax: i64;
-        let ay: i64;
-        let sx: i64;
-        let sy: i64;
-        let dx: i64;
-        let dy: i64;
-
-        dx = x2 - x1;
-        ax = (dx * 2).abs();
-        sx = if dx > 0 { 1 } else { -1 };
-
-        dy = y2 - y1;
-        ay = (dy * 2).abs();
-        sy = if dy > 0 { 1 } else { -1 };
-
-        x = x1;
-        y = y1;
-
-        let b = if dy == 0 { -1 } else { dx / dy };
-        let a = 1;
-        let double_d = (wd * f64::sqrt((a * a + b * b) as f64)) as i64;
-        let delta = double_d / 2;
-
-        if ax > ay {
-            /* x step */
-            d = ay - ax / 2;
-            loop {
-                self.plot_color(x, y, color);
-                {
-                    let total = |_x| {
-                        if dy == 0 {
-                            _x - x1
-                        } else {
-                            _x - (y * dx) / dy + (y1 * dx) / dy - x1
-                        }
-                    };
-                    let mut _x = x;
-                    loop {
-                        let t = total(_x);
-                        if t < -delta || t >= delta {
-                            break;
-                        }
-                        _x += 1;
-                        self.plot_color(_x, y, color);
-                    }
-                    let mut _x = x;
-                    loop {
-                        let t = total(_x);
-                        if t < -delta || t >= delta {
-                            break;
-                        }
-                        _x -= 1;
-                        self.plot_color(_x, y, color);
-                    }
-                }
-                if x == x2 {
-                    return;
-                }
-                if d >= 0 {
-                    y += sy;
-                    d -= ax;
-                }
-                x += sx;
-                d += ay;
-            }
-        } else {
-            /* y step */
-            d = ax - ay / 2;
-            loop {
-                self.plot_color(x, y, color);
-                {
-                    let total = |_x| {
-                        if dy == 0 {
-                            _x - x1
-                        } else {
-                            _x - (y * dx) / dy + (y1 * dx) / dy - x1
-                        }
-                    };
-                    let mut _x = x;
-                    loop {
-                        let t = total(_x);
-                        if t < -delta || t >= delta {
-                            break;
-                        }
-                        _x += 1;
-                        self.plot_color(_x, y, color);
-                    }
-                    let mut _x = x;
-                    loop {
-                        let t = total(_x);
-                        if t < -delta || t >= delta {
-                            break;
-                        }
-                        _x -= 1;
-                        self.plot_color(_x, y, color);
-                    }
-                }
-                if y == y2 {
-                    return;
-                }
-                if d >= 0 {
-                    x += sx;
-                    d -= ay;
-                }
-                y += sy;
-                d += ax;
+        if wd > 1. {
+            // Round caps keep lines drawn end to end connected.
+            let stroke = Stroke {
+                cap: Cap::Round,
+                color,
+                ..Stroke::new(wd)
+            };
+            self.stroke_line((x1, y1), (x2, y2), &stroke);
+            return;
+        }
+
+        /* Bresenham's line algorithm, in closed form so that only the steps inside the clip
+         * rect are walked: after k steps along the major axis, the minor one has moved by
+         * k * minor / major, rounded half away from the start. */
+        let steps = match self.visible_steps((x1, y1), (x2, y2)) {
+            Some(steps) => steps,
+            None => return,
+        };
+        let (dx, dy) = ((x2 - x1).abs(), (y2 - y1).abs());
+        let sx = if x1 < x2 { 1 } else { -1 };
+        let sy = if y1 < y2 { 1 } else { -1 };
+        let (major, minor) = (dx.max(dy), dx.min(dy));
+        for k in steps {
+            let m = if major == 0 {
+                0
+            } else {
+                (2 * k * minor + major) / (2 * major)
+            };
+            if dx >= dy {
+                self.plot_color(x1 + sx * k, y1 + sy * m, color);
+            } else {
+                self.plot_color(x1 + sx * m, y1 + sy * k, color);
             }
         }
     }
@@ -533,17 +467,43 @@ impl Image {
         }
     }
 
+    /// Draws `s` with its first line's top at `y`. Glyphs are positioned on the baseline by their
+    /// metrics, so proportional fonts are spaced by their advance widths.
     pub fn write_str(&mut self, font: &BitmapFont, s: &str, (x, y): (i64, i64)) {
-        for (i, c) in s.chars().enumerate() {
-            let glyph = font.glyph(c).unwrap();
-            self.copy(
-                &glyph,
-                (x as usize + i * font.glyph_width, y as usize),
-                (0, 0),
-                font.glyph_width,
-                font.glyph_height,
-            );
+        let baseline = y + font.ascent as i64;
+        let mut pen = x;
+        for c in s.chars() {
+            let idx = match font.glyph_index_or_replacement(c) {
+                Some(idx) => idx,
+                None => continue,
+            };
+            let metrics = font.glyph_metrics(idx);
+            if let Some(glyph) = font.glyph_at(idx) {
+                self.copy_at(
+                    &glyph,
+                    (
+                        pen + metrics.x_offset,
+                        baseline - metrics.y_offset - metrics.height as i64,
+                    ),
+                );
+            }
+            pen += metrics.advance;
+        }
+    }
+
+    /// Like `copy` for a whole `source`, but `(x, y)` may lie partly outside the image.
+    pub fn copy_at(&mut self, source: &Image, (x, y): (i64, i64)) {
+        let (sx, sy) = ((-x).max(0), (-y).max(0));
+        if sx >= source.width as i64 || sy >= source.height as i64 {
+            return;
         }
+        self.copy(
+            source,
+            (x.max(0) as usize, y.max(0) as usize),
+            (sx as usize, sy as usize),
+            source.width - sx as usize,
+            source.height - sy as usize,
+        );
     }
 
     pub fn resize(
@@ -601,12 +561,54 @@ pub fn bits_to_bytes(bits: &[u8], width: usize) -> Vec<u32> {
     ret
 }
 
+/// Inverse of `bits_to_bytes`: packs each row LSB first and pads it to a whole byte. Only `BLACK`
+/// pixels are set.
+pub fn bytes_to_bits(bytes: &[u32], width: usize) -> Vec<u8> {
+    let mut ret = Vec::with_capacity(bytes.len() / 8 + 1);
+    if width == 0 {
+        return ret;
+    }
+    for row in bytes.chunks(width) {
+        for byte in row.chunks(8) {
+            let mut b = 0_u8;
+            for (n, pixel) in byte.iter().enumerate() {
+                if *pixel == BLACK {
+                    b |= 1 << n;
+                }
+            }
+            ret.push(b);
+        }
+    }
+    ret
+}
+
+/// Placement of a single glyph relative to the pen position on the baseline. Offsets grow right
+/// and up, as in BDF files.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GlyphMetrics {
+    pub width: usize,
+    pub height: usize,
+    pub x_offset: i64,
+    pub y_offset: i64,
+    pub advance: i64,
+}
+
+/// A font whose glyphs are cells of `glyph_width` by `glyph_height` pixels in `image`.
+///
+/// Without an `encoding` map a character's cell index is its code point modulo 256, which is how
+/// sheets like `bizcat.xbm.rs` are laid out. Glyphs that don't fill their cell are drawn from its
+/// top left corner and described by `metrics`.
 pub struct BitmapFont {
     pub image: Image,
     pub x_offset: usize,
     pub y_offset: usize,
     pub glyph_width: usize,
     pub glyph_height: usize,
+    pub ascent: usize,
+    pub descent: usize,
+    pub encoding: Option<HashMap<char, usize>>,
+    pub metrics: Vec<GlyphMetrics>,
+    pub default_char: Option<char>,
 }
 
 impl BitmapFont {
@@ -622,53 +624,66 @@ impl BitmapFont {
             y_offset,
             glyph_width,
             glyph_height,
+            ascent: glyph_height,
+            descent: 0,
+            encoding: None,
+            metrics: vec![],
+            default_char: None,
         }
     }
 
-    pub fn glyph(&self, c: char) -> Option<Image> {
-        let gwidth = (self.image.width - self.x_offset) / self.glyph_width;
+    pub fn glyph_count(&self) -> usize {
+        let columns = (self.image.width - self.x_offset) / self.glyph_width;
+        let rows = (self.image.height - self.y_offset) / self.glyph_height;
+        columns * rows
+    }
+
+    pub fn glyph_index(&self, c: char) -> Option<usize> {
+        let idx = match self.encoding {
+            Some(ref encoding) => *encoding.get(&c)?,
+            None => (c as u32 & 0x00FF) as usize,
+        };
+        if idx < self.glyph_count() {
+            Some(idx)
+        } else {
+            None
+        }
+    }
 
-        let idx = c as u32;
-        let row = (idx & 0x00FF) as usize;
+    pub fn glyph_metrics(&self, idx: usize) -> GlyphMetrics {
+        self.metrics.get(idx).copied().unwrap_or(GlyphMetrics {
+            width: self.glyph_width,
+            height: self.glyph_height,
+            x_offset: 0,
+            y_offset: -(self.descent as i64),
+            advance: self.glyph_width as i64,
+        })
+    }
 
+    /// Returns the glyph cropped to its bounding box.
+    pub fn glyph_at(&self, idx: usize) -> Option<Image> {
+        if idx >= self.glyph_count() {
+            return None;
+        }
+        let gwidth = (self.image.width - self.x_offset) / self.glyph_width;
         let cursor = (
-            self.x_offset + (row % gwidth) * self.glyph_width,
-            self.y_offset + (row / gwidth) * self.glyph_height,
+            self.x_offset + (idx % gwidth) * self.glyph_width,
+            self.y_offset + (idx / gwidth) * self.glyph_height,
         );
+        let metrics = self.glyph_metrics(idx);
 
-        let mut glyph = Image::new(self.glyph_width, self.glyph_height, 0, 0);
+        let mut glyph = Image::new(metrics.width, metrics.height, 0, 0);
         glyph.copy(
             &self.image,
-            (self.x_offset, self.y_offset),
+            (0, 0),
             (cursor.0, cursor.1),
-            self.glyph_width,
-            self.glyph_height,
+            metrics.width,
+            metrics.height,
         );
         Some(glyph)
     }
-}
 
-pub fn distance_line_to_point((x, y): Point, (a, b, c): Line) -> f64 {
-    let d = f64::sqrt((a * a + b * b) as f64);
-    if d == 0.0 {
-        0.
-    } else {
-        (a * x + b * y + c) as f64 / d
-    }
-}
-
-pub fn perpendicular((a, b, _c): Line, p: Point) -> Line {
-    (b, -a, a * p.1 - b * p.0)
-}
-
-pub fn point_perpendicular((a, b, c): Line, p: Point) -> Point {
-    let d = (a * a + b * b) as f64;
-    if d == 0. {
-        return (0, 0);
+    pub fn glyph(&self, c: char) -> Option<Image> {
+        self.glyph_at(self.glyph_index(c)?)
     }
-    let cp = a * p.1 - b * p.0;
-    (
-        ((-a * c - b * cp) as f64 / d) as i64,
-        ((a * cp - b * c) as f64 / d) as i64,
-    )
 }
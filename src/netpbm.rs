@@ -0,0 +1,383 @@
+use crate::{from_u32_rgb, from_u8_rgb, Image, BLACK};
+use std::fs::File;
+use std::io::prelude::*;
+
+type Result<T> = std::result::Result<T, Box<dyn ::std::error::Error>>;
+
+/// Reads the whitespace and `#` comment separated header fields of a Netpbm file.
+struct Header<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Header<'a> {
+    fn skip_whitespace(&mut self) {
+        while let Some(&c) = self.bytes.get(self.pos) {
+            if c == b'#' {
+                while let Some(&c) = self.bytes.get(self.pos) {
+                    if c == b'\n' || c == b'\r' {
+                        break;
+                    }
+                    self.pos += 1;
+                }
+            } else if c.is_ascii_whitespace() {
+                self.pos += 1;
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn number(&mut self, what: &str) -> Result<usize> {
+        self.skip_whitespace();
+        let start = self.pos;
+        while self.bytes.get(self.pos).is_some_and(u8::is_ascii_digit) {
+            self.pos += 1;
+        }
+        if start == self.pos {
+            return Err(
+                format!("Expected {} at byte offset {} of netpbm file", what, start).into(),
+            );
+        }
+        Ok(std::str::from_utf8(&self.bytes[start..self.pos])?.parse::<usize>()?)
+    }
+
+    /// Plain PBM allows bits without any whitespace between them, e.g. `0110`.
+    fn bit(&mut self) -> Result<bool> {
+        self.skip_whitespace();
+        match self.bytes.get(self.pos) {
+            Some(b'0') => {
+                self.pos += 1;
+                Ok(false)
+            }
+            Some(b'1') => {
+                self.pos += 1;
+                Ok(true)
+            }
+            _ => Err(format!("Expected 0 or 1 at byte offset {} of pbm file", self.pos).into()),
+        }
+    }
+
+    /// Raw rasters start after exactly one whitespace character following the header.
+    fn raster(&mut self, len: usize) -> Result<&'a [u8]> {
+        let start = self.pos + 1;
+        start
+            .checked_add(len)
+            .and_then(|end| self.bytes.get(start..end))
+            .ok_or_else(|| "Netpbm raster is shorter than its header says".into())
+    }
+}
+
+fn scale(v: usize, maxval: usize) -> u8 {
+    if maxval == 255 {
+        v.min(maxval) as u8
+    } else {
+        ((v.min(maxval) * 255 + maxval / 2) / maxval) as u8
+    }
+}
+
+fn luma(pixel: u32) -> u8 {
+    let (r, g, b) = from_u32_rgb(pixel);
+    ((299 * r as u32 + 587 * g as u32 + 114 * b as u32 + 500) / 1000) as u8
+}
+
+impl Image {
+    pub fn from_netpbm(path: &str, x_offset: usize, y_offset: usize) -> Result<Self> {
+        let mut file = File::open(path)?;
+        let mut bytes = vec![];
+        file.read_to_end(&mut bytes)?;
+        Self::from_netpbm_bytes(&bytes, x_offset, y_offset)
+    }
+
+    /// Decodes any of the P1-P6 formats. Gray and color samples are scaled from `maxval` to 8
+    /// bits.
+    pub fn from_netpbm_bytes(bytes: &[u8], x_offset: usize, y_offset: usize) -> Result<Self> {
+        let magic = match bytes {
+            [b'P', n @ b'1'..=b'6', ..] => n - b'0',
+            _ => return Err("Not a netpbm file, missing P1-P6 magic number".into()),
+        };
+        let mut header = Header { bytes, pos: 2 };
+        let width = header.number("width")?;
+        let height = header.number("height")?;
+        let maxval = if matches!(magic, 1 | 4) {
+            1
+        } else {
+            header.number("maxval")?
+        };
+        if maxval == 0 || maxval > 65535 {
+            return Err(format!("Invalid netpbm maxval {}", maxval).into());
+        }
+        let channels = if matches!(magic, 3 | 6) { 3 } else { 1 };
+        let sample_bytes = if maxval > 255 { 2 } else { 1 };
+        // The size of a raw raster, or the least a plain one can take with one character per
+        // sample, checked before allocating anything as large as the header asks.
+        let raster_len = match magic {
+            1 => width.checked_mul(height),
+            2 | 3 => width
+                .checked_mul(height)
+                .and_then(|n| n.checked_mul(channels)),
+            4 => width.div_ceil(8).checked_mul(height),
+            _ => width
+                .checked_mul(height)
+                .and_then(|n| n.checked_mul(channels * sample_bytes)),
+        }
+        .ok_or("Netpbm dimensions are too large")?;
+        if raster_len > bytes.len() - header.pos {
+            return Err("Netpbm raster is shorter than its header says".into());
+        }
+        let mut ret = Image::new(width, height, x_offset, y_offset);
+        match magic {
+            1 => {
+                for p in ret.bytes.iter_mut() {
+                    if header.bit()? {
+                        *p = BLACK;
+                    }
+                }
+            }
+            4 => {
+                let row_bytes = width.div_ceil(8);
+                let raster = header.raster(raster_len)?;
+                for (y, row) in raster.chunks(row_bytes.max(1)).enumerate().take(height) {
+                    for x in 0..width {
+                        if row[x / 8] & (0x80 >> (x % 8)) != 0 {
+                            ret.bytes[y * width + x] = BLACK;
+                        }
+                    }
+                }
+            }
+            2 | 3 => {
+                let mut samples = [0_u8; 3];
+                for p in ret.bytes.iter_mut() {
+                    for s in samples.iter_mut().take(channels) {
+                        *s = scale(header.number("sample")?, maxval);
+                    }
+                    *p = if channels == 1 {
+                        from_u8_rgb(samples[0], samples[0], samples[0])
+                    } else {
+                        from_u8_rgb(samples[0], samples[1], samples[2])
+                    };
+                }
+            }
+            _ => {
+                let raster = header.raster(raster_len)?;
+                let samples = raster
+                    .chunks(sample_bytes)
+                    .map(|s| {
+                        let v = if sample_bytes == 2 {
+                            ((s[0] as usize) << 8) | s[1] as usize
+                        } else {
+                            s[0] as usize
+                        };
+                        scale(v, maxval)
+                    })
+                    .collect::<Vec<u8>>();
+                for (p, s) in ret.bytes.iter_mut().zip(samples.chunks(channels)) {
+                    *p = if channels == 1 {
+                        from_u8_rgb(s[0], s[0], s[0])
+                    } else {
+                        from_u8_rgb(s[0], s[1], s[2])
+                    };
+                }
+            }
+        }
+        Ok(ret)
+    }
+
+    /// Encodes as P1 (`raw == false`) or P4. Only `BLACK` pixels are written as set bits.
+    pub fn to_pbm(&self, raw: bool) -> Vec<u8> {
+        let mut ret = format!(
+            "P{}\n{} {}\n",
+            if raw { 4 } else { 1 },
+            self.width,
+            self.height
+        )
+        .into_bytes();
+        for row in self.bytes.chunks(self.width.max(1)) {
+            if raw {
+                for byte in row.chunks(8) {
+                    ret.push(
+                        byte.iter()
+                            .enumerate()
+                            .filter(|(_, p)| **p == BLACK)
+                            .fold(0, |acc, (i, _)| acc | (0x80 >> i)),
+                    );
+                }
+            } else {
+                // Plain PBM lines must not be longer than 70 characters.
+                for line in row.chunks(35) {
+                    for (i, p) in line.iter().enumerate() {
+                        if i > 0 {
+                            ret.push(b' ');
+                        }
+                        ret.push(if *p == BLACK { b'1' } else { b'0' });
+                    }
+                    ret.push(b'\n');
+                }
+            }
+        }
+        ret
+    }
+
+    /// Encodes as P2 (`raw == false`) or P5 using the Rec. 601 luma of each pixel.
+    pub fn to_pgm(&self, raw: bool) -> Vec<u8> {
+        let mut ret = format!(
+            "P{}\n{} {}\n255\n",
+            if raw { 5 } else { 2 },
+            self.width,
+            self.height
+        )
+        .into_bytes();
+        for row in self.bytes.chunks(self.width.max(1)) {
+            if raw {
+                ret.extend(row.iter().map(|p| luma(*p)));
+            } else {
+                for line in row.chunks(17) {
+                    let line = line
+                        .iter()
+                        .map(|p| luma(*p).to_string())
+                        .collect::<Vec<String>>();
+                    ret.extend(line.join(" ").bytes());
+                    ret.push(b'\n');
+                }
+            }
+        }
+        ret
+    }
+
+    /// Encodes as P3 (`raw == false`) or P6.
+    pub fn to_ppm(&self, raw: bool) -> Vec<u8> {
+        let mut ret = format!(
+            "P{}\n{} {}\n255\n",
+            if raw { 6 } else { 3 },
+            self.width,
+            self.height
+        )
+        .into_bytes();
+        for row in self.bytes.chunks(self.width.max(1)) {
+            if raw {
+                for p in row {
+                    let (r, g, b) = from_u32_rgb(*p);
+                    ret.extend([r, g, b]);
+                }
+            } else {
+                for line in row.chunks(5) {
+                    let line = line
+                        .iter()
+                        .map(|p| {
+                            let (r, g, b) = from_u32_rgb(*p);
+                            format!("{} {} {}", r, g, b)
+                        })
+                        .collect::<Vec<String>>();
+                    ret.extend(line.join("  ").bytes());
+                    ret.push(b'\n');
+                }
+            }
+        }
+        ret
+    }
+
+    pub fn save_pbm(&self, path: &str, raw: bool) -> Result<()> {
+        File::create(path)?.write_all(&self.to_pbm(raw))?;
+        Ok(())
+    }
+
+    pub fn save_pgm(&self, path: &str, raw: bool) -> Result<()> {
+        File::create(path)?.write_all(&self.to_pgm(raw))?;
+        Ok(())
+    }
+
+    pub fn save_ppm(&self, path: &str, raw: bool) -> Result<()> {
+        File::create(path)?.write_all(&self.to_ppm(raw))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::WHITE;
+
+    fn pattern(width: usize, height: usize, color: impl Fn(usize, usize) -> u32) -> Image {
+        let mut ret = Image::new(width, height, 0, 0);
+        for y in 0..height {
+            for x in 0..width {
+                ret.bytes[y * width + x] = color(x, y);
+            }
+        }
+        ret
+    }
+
+    fn decode(bytes: &[u8]) -> Image {
+        Image::from_netpbm_bytes(bytes, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn pbm_round_trip() {
+        // A width that isn't a multiple of 8 exercises the padding of raw rows.
+        let image = pattern(11, 3, |x, y| if (x + y) % 3 == 0 { BLACK } else { WHITE });
+        for raw in [false, true] {
+            let decoded = decode(&image.to_pbm(raw));
+            assert_eq!((decoded.width, decoded.height), (11, 3));
+            assert_eq!(decoded.bytes, image.bytes);
+        }
+    }
+
+    #[test]
+    fn pgm_round_trip() {
+        let image = pattern(7, 5, |x, y| {
+            let v = (x * 37 + y * 11) as u8;
+            from_u8_rgb(v, v, v)
+        });
+        for raw in [false, true] {
+            assert_eq!(decode(&image.to_pgm(raw)).bytes, image.bytes);
+        }
+    }
+
+    #[test]
+    fn ppm_round_trip() {
+        let image = pattern(6, 4, |x, y| {
+            from_u8_rgb(x as u8 * 40, y as u8 * 60, 255 - x as u8)
+        });
+        for raw in [false, true] {
+            assert_eq!(decode(&image.to_ppm(raw)).bytes, image.bytes);
+        }
+    }
+
+    #[test]
+    fn comments_and_maxval() {
+        let image = decode(b"P2\n# a comment\n3 1 # another\n15\n0 15 300\n");
+        let grays = image.bytes.iter().map(|p| from_u32_rgb(*p).0);
+        assert_eq!(grays.collect::<Vec<_>>(), [0, 255, 255]);
+        // Samples above maxval are clamped when maxval is 255 as well.
+        assert_eq!(decode(b"P2 1 1 255 300").bytes, [WHITE]);
+        let image = decode(b"P5 2 1 65535\n\x00\x00\xff\xff");
+        assert_eq!(image.bytes, [BLACK, WHITE]);
+    }
+
+    #[test]
+    fn malformed() {
+        for bytes in [
+            &b"P7 1 1 255\n"[..],
+            b"P6 2 2 255\n\x00\x00\x00",
+            b"P4 9 2\n\x00",
+            b"P1 2 2 0 1 1",
+            b"P2 2 1 255 0 x",
+            b"P3 1 1 0 0 0 0",
+            b"P5 1 1",
+        ] {
+            assert!(Image::from_netpbm_bytes(bytes, 0, 0).is_err());
+        }
+    }
+
+    #[test]
+    fn oversize_dimensions() {
+        for bytes in [
+            &b"P4 18446744073709551615 18446744073709551615\n"[..],
+            b"P6 4294967296 4294967296 255\n",
+            b"P5 100000 100000 255\n\x00",
+            b"P3 100000 100000 255\n0 0 0",
+        ] {
+            assert!(Image::from_netpbm_bytes(bytes, 0, 0).is_err());
+        }
+    }
+}
@@ -0,0 +1,312 @@
+use crate::{BitmapFont, Image, BLACK};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::prelude::*;
+
+type Result<T> = std::result::Result<T, Box<dyn ::std::error::Error>>;
+
+const PSF1_MAGIC: [u8; 2] = [0x36, 0x04];
+const PSF1_MODE512: u8 = 0x01;
+const PSF1_MODEHASTAB: u8 = 0x02;
+const PSF1_MODESEQ: u8 = 0x04;
+const PSF1_SEPARATOR: u16 = 0xFFFF;
+const PSF1_STARTSEQ: u16 = 0xFFFE;
+
+const PSF2_MAGIC: [u8; 4] = [0x72, 0xb5, 0x4a, 0x86];
+const PSF2_HAS_UNICODE_TABLE: u32 = 0x01;
+const PSF2_SEPARATOR: u8 = 0xFF;
+const PSF2_STARTSEQ: u8 = 0xFE;
+const PSF2_HEADER_SIZE: u32 = 32;
+
+/// Glyph cells per row of the sheet a PSF font is unpacked into.
+const SHEET_COLUMNS: usize = 16;
+
+fn u32_at(bytes: &[u8], offset: usize) -> Result<u32> {
+    let b = bytes
+        .get(offset..offset + 4)
+        .ok_or("PSF2 header is truncated")?;
+    Ok(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+impl BitmapFont {
+    /// Loads an uncompressed PC Screen Font, version 1 or 2. Fonts shipped as `.psf.gz` have to
+    /// be decompressed first.
+    pub fn from_psf(path: &str) -> Result<Self> {
+        let mut file = File::open(path)?;
+        let mut bytes = vec![];
+        file.read_to_end(&mut bytes)?;
+        Self::from_psf_bytes(&bytes)
+    }
+
+    /// Without a Unicode table, glyph `n` is mapped to code point `n`. Multi code point sequences
+    /// in the table are skipped, since a `BitmapFont` maps single characters.
+    pub fn from_psf_bytes(bytes: &[u8]) -> Result<Self> {
+        let mut encoding = HashMap::new();
+        let (width, height, count, glyphs) = if bytes.starts_with(&PSF1_MAGIC) {
+            let (mode, charsize) = match bytes {
+                [_, _, mode, charsize, ..] => (*mode, *charsize as usize),
+                _ => return Err("PSF1 header is truncated".into()),
+            };
+            let count = if mode & PSF1_MODE512 != 0 { 512 } else { 256 };
+            let glyphs = bytes
+                .get(4..4 + count * charsize)
+                .ok_or("PSF1 glyph data is truncated")?;
+            if mode & (PSF1_MODEHASTAB | PSF1_MODESEQ) != 0 {
+                let table = bytes[4 + count * charsize..]
+                    .chunks_exact(2)
+                    .map(|c| u16::from_le_bytes([c[0], c[1]]));
+                let (mut glyph, mut in_sequence) = (0, false);
+                for entry in table {
+                    match entry {
+                        PSF1_SEPARATOR => {
+                            glyph += 1;
+                            in_sequence = false;
+                        }
+                        PSF1_STARTSEQ => in_sequence = true,
+                        c if !in_sequence && glyph < count => {
+                            if let Some(c) = char::from_u32(c as u32) {
+                                encoding.entry(c).or_insert(glyph);
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            (8, charsize, count, glyphs)
+        } else if bytes.starts_with(&PSF2_MAGIC) {
+            let header_size = u32_at(bytes, 8)? as usize;
+            if header_size < PSF2_HEADER_SIZE as usize {
+                return Err("PSF2 header size is smaller than the header".into());
+            }
+            let flags = u32_at(bytes, 12)?;
+            let count = u32_at(bytes, 16)? as usize;
+            let charsize = u32_at(bytes, 20)? as usize;
+            let height = u32_at(bytes, 24)? as usize;
+            let width = u32_at(bytes, 28)? as usize;
+            if width.div_ceil(8).checked_mul(height) != Some(charsize) {
+                return Err("PSF2 charsize doesn't match the glyph dimensions".into());
+            }
+            let glyphs_end = count
+                .checked_mul(charsize)
+                .and_then(|n| n.checked_add(header_size))
+                .ok_or("PSF2 glyph data is truncated")?;
+            let glyphs = bytes
+                .get(header_size..glyphs_end)
+                .ok_or("PSF2 glyph data is truncated")?;
+            if flags & PSF2_HAS_UNICODE_TABLE != 0 {
+                let table = &bytes[glyphs_end..];
+                for (glyph, entries) in table.split(|b| *b == PSF2_SEPARATOR).enumerate() {
+                    if glyph >= count {
+                        break;
+                    }
+                    let single = match entries.iter().position(|b| *b == PSF2_STARTSEQ) {
+                        Some(seq) => &entries[..seq],
+                        None => entries,
+                    };
+                    for c in String::from_utf8_lossy(single).chars() {
+                        if c != char::REPLACEMENT_CHARACTER {
+                            encoding.entry(c).or_insert(glyph);
+                        }
+                    }
+                }
+            }
+            (width, height, count, glyphs)
+        } else {
+            return Err("Not a PSF file, bad magic number".into());
+        };
+        if width == 0 || height == 0 {
+            return Err("PSF font has empty glyphs".into());
+        }
+        if encoding.is_empty() {
+            encoding.extend((0..count).filter_map(|i| Some((char::from_u32(i as u32)?, i))));
+        }
+
+        let bytes_per_row = width.div_ceil(8);
+        let rows = count.div_ceil(SHEET_COLUMNS);
+        let mut image = Image::new(SHEET_COLUMNS * width, rows * height, 0, 0);
+        for (i, glyph) in glyphs.chunks(bytes_per_row * height).enumerate() {
+            let (cx, cy) = (
+                ((i % SHEET_COLUMNS) * width) as i64,
+                ((i / SHEET_COLUMNS) * height) as i64,
+            );
+            for (y, row) in glyph.chunks(bytes_per_row).enumerate() {
+                for x in 0..width {
+                    if row[x / 8] & (0x80 >> (x % 8)) != 0 {
+                        image.plot(cx + x as i64, cy + y as i64);
+                    }
+                }
+            }
+        }
+        let mut ret = BitmapFont::new(image, (width, height), 0, 0);
+        ret.encoding = Some(encoding);
+        Ok(ret)
+    }
+
+    /// Encodes every cell of the font as a PSF2 glyph, with a Unicode table built from the
+    /// font's `encoding`. Glyphs are placed in their cells by their metrics, so the exported
+    /// cells share one baseline.
+    pub fn to_psf2(&self) -> Result<Vec<u8>> {
+        if self.glyph_width == 0 || self.glyph_height == 0 {
+            return Err("PSF font has empty glyphs".into());
+        }
+        let count = self.glyph_count();
+        let width = self.glyph_width;
+        let height = self.glyph_height.max(self.ascent + self.descent);
+        let bytes_per_row = width.div_ceil(8);
+        let to_u32 = |n: usize| u32::try_from(n).map_err(|_| "font is too large for PSF2");
+        let charsize = bytes_per_row
+            .checked_mul(height)
+            .ok_or("font is too large for PSF2")?;
+
+        let mut ret = Vec::with_capacity(PSF2_HEADER_SIZE as usize + count * charsize);
+        ret.extend_from_slice(&PSF2_MAGIC);
+        for field in [
+            0,
+            PSF2_HEADER_SIZE,
+            PSF2_HAS_UNICODE_TABLE,
+            to_u32(count)?,
+            to_u32(charsize)?,
+            to_u32(height)?,
+            to_u32(width)?,
+        ] {
+            ret.extend_from_slice(&field.to_le_bytes());
+        }
+
+        for idx in 0..count {
+            let metrics = self.glyph_metrics(idx);
+            let mut cell = Image::new(width, height, 0, 0);
+            if let Some(glyph) = self.glyph_at(idx) {
+                cell.copy_at(
+                    &glyph,
+                    (
+                        metrics.x_offset,
+                        self.ascent as i64 - metrics.y_offset - metrics.height as i64,
+                    ),
+                );
+            }
+            for row in cell.bytes.chunks(width) {
+                let mut packed = vec![0_u8; bytes_per_row];
+                for (x, p) in row.iter().enumerate() {
+                    if *p == BLACK {
+                        packed[x / 8] |= 0x80 >> (x % 8);
+                    }
+                }
+                ret.extend_from_slice(&packed);
+            }
+        }
+
+        let mut table = vec![vec![]; count];
+        match self.encoding {
+            Some(ref encoding) => {
+                for (c, idx) in encoding.iter() {
+                    if *idx < count {
+                        table[*idx].push(*c);
+                    }
+                }
+            }
+            None => {
+                for (idx, chars) in table.iter_mut().enumerate().take(256) {
+                    chars.extend(char::from_u32(idx as u32));
+                }
+            }
+        }
+        let mut buf = [0; 4];
+        for mut chars in table {
+            chars.sort_unstable();
+            for c in chars {
+                ret.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+            }
+            ret.push(PSF2_SEPARATOR);
+        }
+        Ok(ret)
+    }
+
+    pub fn save_psf2(&self, path: &str) -> Result<()> {
+        File::create(path)?.write_all(&self.to_psf2()?)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A PSF2 font of 5 × 2 glyphs whose Unicode table maps the first two to `a` and `b`.
+    fn psf2(count: u32, charsize: u32, glyphs: &[u8]) -> Vec<u8> {
+        let mut ret = PSF2_MAGIC.to_vec();
+        for field in [
+            0,
+            PSF2_HEADER_SIZE,
+            PSF2_HAS_UNICODE_TABLE,
+            count,
+            charsize,
+            2,
+            5,
+        ] {
+            ret.extend_from_slice(&field.to_le_bytes());
+        }
+        ret.extend_from_slice(glyphs);
+        ret.extend_from_slice(b"a\xffb\xff");
+        ret
+    }
+
+    fn black(image: &Image) -> Vec<bool> {
+        image.bytes.iter().map(|p| *p == BLACK).collect()
+    }
+
+    #[test]
+    fn psf2_round_trip() {
+        let font = BitmapFont::from_psf_bytes(&psf2(2, 2, &[0xf8, 0x88, 0x20, 0x50])).unwrap();
+        assert_eq!(
+            black(&font.glyph('a').unwrap()),
+            [true, true, true, true, true, true, false, false, false, true]
+        );
+        let again = BitmapFont::from_psf_bytes(&font.to_psf2().unwrap()).unwrap();
+        assert_eq!((again.glyph_width, again.glyph_height), (5, 2));
+        for c in ['a', 'b'] {
+            assert_eq!(
+                black(&again.glyph(c).unwrap()),
+                black(&font.glyph(c).unwrap())
+            );
+        }
+    }
+
+    #[test]
+    fn psf1() {
+        let mut bytes = vec![PSF1_MAGIC[0], PSF1_MAGIC[1], 0, 1];
+        bytes.extend((0..=255).map(|i| if i == b'x' { 0x81 } else { 0 }));
+        let font = BitmapFont::from_psf_bytes(&bytes).unwrap();
+        let glyph = black(&font.glyph('x').unwrap());
+        assert_eq!(glyph.iter().filter(|b| **b).count(), 2);
+        assert!(glyph[0] && glyph[7]);
+    }
+
+    #[test]
+    fn malformed() {
+        let font = psf2(2, 2, &[0xf8, 0x88, 0x20, 0x50]);
+        assert!(BitmapFont::from_psf_bytes(&font[..20]).is_err());
+        assert!(BitmapFont::from_psf_bytes(&psf2(2, 2, &[0xf8, 0x88, 0x20])[..35]).is_err());
+        assert!(BitmapFont::from_psf_bytes(&psf2(2, 3, &[0; 6])).is_err());
+        assert!(BitmapFont::from_psf_bytes(&[PSF1_MAGIC[0], PSF1_MAGIC[1], 0, 8, 0]).is_err());
+        assert!(BitmapFont::from_psf_bytes(b"not a font").is_err());
+        let mut short_header = font.clone();
+        short_header[8] = 16;
+        assert!(BitmapFont::from_psf_bytes(&short_header).is_err());
+    }
+
+    #[test]
+    fn empty_glyphs() {
+        let mut font = BitmapFont::from_psf_bytes(&psf2(2, 2, &[0; 4])).unwrap();
+        font.glyph_width = 0;
+        assert!(font.to_psf2().is_err());
+    }
+
+    #[test]
+    fn oversize_dimensions() {
+        let mut font = psf2(u32::MAX, 2, &[0; 4]);
+        assert!(BitmapFont::from_psf_bytes(&font).is_err());
+        // A charsize that only matches the dimensions if their product overflows.
+        font[24..32].copy_from_slice(&[0xff; 8]);
+        assert!(BitmapFont::from_psf_bytes(&font).is_err());
+    }
+}
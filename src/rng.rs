@@ -0,0 +1,28 @@
+/// A xorshift generator for shuffles that have to be reproducible. `rand` doesn't promise the
+/// same sequence for a seed across its versions, while this one always gives the same enclosing
+/// circles and blue-noise maps.
+pub(crate) struct XorShift(u64);
+
+impl XorShift {
+    /// Zero, which would only ever give zeros, is replaced by another seed.
+    pub(crate) fn new(seed: u64) -> Self {
+        Self(if seed == 0 {
+            0x2545_f491_4f6c_dd1d
+        } else {
+            seed
+        })
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    pub(crate) fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            items.swap(i, (self.next() % (i as u64 + 1)) as usize);
+        }
+    }
+}
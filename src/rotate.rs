@@ -0,0 +1,258 @@
+//! Rotation by shearing, after Paeth ("A Fast Algorithm for General Raster Rotation",
+//! Graphics Gems) and Tanaka et al. Each shear only moves whole rows or columns of pixels, so
+//! unlike resampling with `Image::warp`, no pixel is lost or duplicated.
+
+use std::f64::consts::{FRAC_PI_2, FRAC_PI_4, PI};
+
+use crate::{Image, WHITE};
+
+/// An image under rotation, where `None` marks pixels outside the rotated source.
+struct Canvas {
+    width: usize,
+    height: usize,
+    pixels: Vec<Option<u32>>,
+    hotspot: Option<(i64, i64)>,
+}
+
+impl Canvas {
+    fn new(image: &Image) -> Self {
+        Self {
+            width: image.width,
+            height: image.height,
+            pixels: image.bytes.iter().map(|p| Some(*p)).collect(),
+            hotspot: image.hotspot.map(|(x, y)| (x as i64, y as i64)),
+        }
+    }
+
+    /// Moves row `y` right by `k * (y - center)` rounded, growing the canvas to fit.
+    fn shear_rows(&mut self, k: f64) {
+        let center = (self.height as f64 - 1.) / 2.;
+        let shifts = (0..self.height)
+            .map(|y| (k * (y as f64 - center)).round() as i64)
+            .collect::<Vec<_>>();
+        let min = shifts.iter().copied().min().unwrap_or(0);
+        let max = shifts.iter().copied().max().unwrap_or(0);
+        let width = self.width + (max - min) as usize;
+        let mut pixels = vec![None; width * self.height];
+        for (y, shift) in shifts.iter().enumerate() {
+            let start = y * width + (shift - min) as usize;
+            pixels[start..start + self.width]
+                .copy_from_slice(&self.pixels[y * self.width..(y + 1) * self.width]);
+        }
+        self.hotspot = self.hotspot.map(|(x, y)| (x + shifts[y as usize] - min, y));
+        self.width = width;
+        self.pixels = pixels;
+    }
+
+    fn transpose(&mut self) {
+        let mut pixels = vec![None; self.pixels.len()];
+        for y in 0..self.height {
+            for x in 0..self.width {
+                pixels[x * self.height + y] = self.pixels[y * self.width + x];
+            }
+        }
+        std::mem::swap(&mut self.width, &mut self.height);
+        self.hotspot = self.hotspot.map(|(x, y)| (y, x));
+        self.pixels = pixels;
+    }
+
+    /// Moves column `x` down by `k * (x - center)` rounded.
+    fn shear_columns(&mut self, k: f64) {
+        self.transpose();
+        self.shear_rows(k);
+        self.transpose();
+    }
+
+    /// Crops away the rows and columns that are entirely outside the rotated source.
+    fn into_image(self, x_offset: usize, y_offset: usize) -> Image {
+        let inside = |x: usize, y: usize| self.pixels[y * self.width + x].is_some();
+        let rows = (0..self.height)
+            .filter(|y| (0..self.width).any(|x| inside(x, *y)))
+            .collect::<Vec<_>>();
+        let cols = (0..self.width)
+            .filter(|x| (0..self.height).any(|y| inside(*x, y)))
+            .collect::<Vec<_>>();
+        let (x0, y0) = match (cols.first(), rows.first()) {
+            (Some(x0), Some(y0)) => (*x0, *y0),
+            _ => return Image::new(0, 0, x_offset, y_offset),
+        };
+        let width = cols.last().unwrap() - x0 + 1;
+        let height = rows.last().unwrap() - y0 + 1;
+        let mut ret = Image::new(width, height, x_offset, y_offset);
+        for y in 0..height {
+            for x in 0..width {
+                ret.bytes[y * width + x] =
+                    self.pixels[(y + y0) * self.width + x + x0].unwrap_or(WHITE);
+            }
+        }
+        ret.hotspot = self
+            .hotspot
+            .map(|(x, y)| ((x - x0 as i64) as usize, (y - y0 as i64) as usize));
+        ret
+    }
+}
+
+impl Image {
+    fn remap(
+        &self,
+        width: usize,
+        height: usize,
+        source: impl Fn(usize, usize) -> (usize, usize),
+    ) -> Image {
+        let mut ret = Image::new(width, height, self.x_offset, self.y_offset);
+        for y in 0..height {
+            for x in 0..width {
+                let (sx, sy) = source(x, y);
+                ret.bytes[y * width + x] = self.bytes[sy * self.width + sx];
+            }
+        }
+        ret
+    }
+
+    /// Rotates a quarter turn clockwise.
+    pub fn rotate_90(&self) -> Image {
+        let h = self.height;
+        let mut ret = self.remap(h, self.width, |x, y| (y, h - 1 - x));
+        ret.hotspot = self.hotspot.map(|(x, y)| (h - 1 - y, x));
+        ret
+    }
+
+    pub fn rotate_180(&self) -> Image {
+        let (w, h) = (self.width, self.height);
+        let mut ret = self.remap(w, h, |x, y| (w - 1 - x, h - 1 - y));
+        ret.hotspot = self.hotspot.map(|(x, y)| (w - 1 - x, h - 1 - y));
+        ret
+    }
+
+    /// Rotates a quarter turn counterclockwise.
+    pub fn rotate_270(&self) -> Image {
+        let w = self.width;
+        let mut ret = self.remap(self.height, w, |x, y| (w - 1 - y, x));
+        ret.hotspot = self.hotspot.map(|(x, y)| (y, w - 1 - x));
+        ret
+    }
+
+    /// Mirrors left to right.
+    pub fn flip_horizontal(&self) -> Image {
+        let w = self.width;
+        let mut ret = self.remap(w, self.height, |x, y| (w - 1 - x, y));
+        ret.hotspot = self.hotspot.map(|(x, y)| (w - 1 - x, y));
+        ret
+    }
+
+    /// Mirrors top to bottom.
+    pub fn flip_vertical(&self) -> Image {
+        let h = self.height;
+        let mut ret = self.remap(self.width, h, |x, y| (x, h - 1 - y));
+        ret.hotspot = self.hotspot.map(|(x, y)| (x, h - 1 - y));
+        ret
+    }
+
+    /// Rotates by `angle` radians about the center, clockwise on screen like
+    /// `Affine::rotate`. Multiples of a quarter turn are exact; other angles are done with a
+    /// quarter turn and three shears of at most 45°. Every pixel is moved to exactly one
+    /// destination pixel, so the number of `BLACK` pixels is preserved and no holes appear.
+    /// The result is cropped to the rotated image.
+    pub fn rotate(&self, angle: f64) -> Image {
+        let angle = angle.rem_euclid(2. * PI);
+        let quarters = (angle / FRAC_PI_2).round();
+        let rest = angle - quarters * FRAC_PI_2;
+        let turned = match quarters as i64 % 4 {
+            0 => self.clone(),
+            1 => self.rotate_90(),
+            2 => self.rotate_180(),
+            _ => self.rotate_270(),
+        };
+        if rest.abs() < 1e-9 {
+            return turned;
+        }
+        debug_assert!(rest.abs() <= FRAC_PI_4 + 1e-9);
+        // The rotation matrix factors into shears along x, then y, then x again.
+        let (alpha, beta) = (-(rest / 2.).tan(), rest.sin());
+        let mut canvas = Canvas::new(&turned);
+        canvas.shear_rows(alpha);
+        canvas.shear_columns(beta);
+        canvas.shear_rows(alpha);
+        canvas.into_image(self.x_offset, self.y_offset)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BLACK;
+
+    /// An L with a dot, with no symmetries.
+    fn sprite() -> Image {
+        let mut image = Image::new(5, 4, 0, 0);
+        for p in [(0, 0), (0, 1), (0, 2), (1, 2), (2, 2), (4, 0)] {
+            image.plot(p.0, p.1);
+        }
+        image.hotspot = Some((4, 0));
+        image
+    }
+
+    fn same(a: &Image, b: &Image) -> bool {
+        (a.width, a.height, &a.bytes, a.hotspot) == (b.width, b.height, &b.bytes, b.hotspot)
+    }
+
+    #[test]
+    fn quarter_turns() {
+        let image = sprite();
+        let turned = image.rotate_90();
+        assert_eq!((turned.width, turned.height), (4, 5));
+        // The top left corner goes to the top right, and the top right one to the bottom right.
+        assert_eq!(turned.get(3, 0), Some(BLACK));
+        assert_eq!(turned.get(3, 4), Some(BLACK));
+        assert_eq!(turned.hotspot, Some((3, 4)));
+
+        assert!(same(&turned.rotate_90(), &image.rotate_180()));
+        assert!(same(&turned.rotate_90().rotate_90(), &image.rotate_270()));
+        assert!(same(&image.rotate_270().rotate_90(), &image));
+        assert!(same(
+            &image.flip_horizontal().flip_vertical(),
+            &image.rotate_180()
+        ));
+        assert!(same(&image.flip_vertical().flip_vertical(), &image));
+        assert!(same(
+            &image.flip_horizontal().rotate_90(),
+            &image.rotate_90().flip_vertical()
+        ));
+
+        assert!(same(&image.rotate(FRAC_PI_2), &turned));
+        assert!(same(&image.rotate(-FRAC_PI_2), &image.rotate_270()));
+        assert!(same(&image.rotate(5. * PI), &image.rotate_180()));
+    }
+
+    #[test]
+    fn keeps_pixels() {
+        let mut image = Image::new(24, 17, 0, 0);
+        for y in 0..17 {
+            for x in 0..24 {
+                if (x * 7 + y * 3) % 5 == 0 || x == y {
+                    image.plot(x, y);
+                }
+            }
+        }
+        let black = image.black_pixels().len();
+        for degrees in [1., 10., 30., 44., 45., 46., 100., 200., 315., -20.] {
+            let rotated = image.rotate(f64::to_radians(degrees));
+            assert_eq!(rotated.black_pixels().len(), black, "{degrees}°");
+        }
+        // A small rotation grows the bounds by about the shear of each side.
+        let rotated = image.rotate(f64::to_radians(10.));
+        assert!((26..=28).contains(&rotated.width), "{}", rotated.width);
+        assert!((20..=22).contains(&rotated.height), "{}", rotated.height);
+    }
+
+    #[test]
+    fn hotspot() {
+        let mut image = Image::new(9, 9, 0, 0);
+        image.plot(4, 4);
+        image.hotspot = Some((4, 4));
+        let rotated = image.rotate(f64::to_radians(30.));
+        let (hx, hy) = rotated.hotspot.unwrap();
+        assert_eq!(rotated.get(hx as i64, hy as i64), Some(BLACK));
+        assert_eq!(rotated.black_pixels(), [(hx as i64, hy as i64)]);
+    }
+}
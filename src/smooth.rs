@@ -0,0 +1,289 @@
+//! Rule-based smoothing of enlarged bitmaps. The image is scaled up by pixel replication, then
+//! every place where a rule's pattern matches the original gets the rule's polygon filled in,
+//! which cuts the staircase steps of diagonal edges.
+
+use crate::{Image, Point, BLACK};
+
+/// What a cell of a rule's pattern must be for the rule to match.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RuleTile {
+    /// Not `BLACK`.
+    Blank,
+    /// `BLACK`.
+    Filled,
+    /// Not `BLACK`, and part of the area the rule fills in.
+    Smooth,
+    /// Anything, even outside the image.
+    Ignore,
+}
+
+use RuleTile::*;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SmoothingRule {
+    /// Rows of cells, all of the same length.
+    pub pattern: Vec<Vec<RuleTile>>,
+    /// The convex polygon filled with `BLACK` where the pattern matches, in units of original
+    /// pixels from the pattern's top left corner, so that cell `(x, y)` spans `x..x + 1` and
+    /// `y..y + 1`.
+    pub fill: Vec<(f64, f64)>,
+}
+
+impl SmoothingRule {
+    pub fn new(pattern: Vec<Vec<RuleTile>>, fill: Vec<(f64, f64)>) -> Self {
+        Self { pattern, fill }
+    }
+
+    pub fn width(&self) -> usize {
+        self.pattern.first().map_or(0, |row| row.len())
+    }
+
+    pub fn height(&self) -> usize {
+        self.pattern.len()
+    }
+
+    /// Whether the pattern matches with its top left cell on pixel `(x, y)` of `image`. Cells
+    /// other than `Ignore` never match outside the image.
+    pub fn matches(&self, image: &Image, (x, y): Point) -> bool {
+        self.pattern.iter().enumerate().all(|(row, tiles)| {
+            tiles.iter().enumerate().all(|(col, tile)| {
+                match (tile, image.get(x + col as i64, y + row as i64)) {
+                    (Ignore, _) => true,
+                    (_, None) => false,
+                    (Filled, Some(p)) => p == BLACK,
+                    (Blank | Smooth, Some(p)) => p != BLACK,
+                }
+            })
+        })
+    }
+
+    /// The rule turned a quarter turn clockwise.
+    pub fn rotate_90(&self) -> Self {
+        let height = self.height();
+        Self {
+            pattern: (0..self.width())
+                .map(|col| {
+                    (0..height)
+                        .rev()
+                        .map(|row| self.pattern[row][col])
+                        .collect()
+                })
+                .collect(),
+            fill: self
+                .fill
+                .iter()
+                .map(|(x, y)| (height as f64 - y, *x))
+                .collect(),
+        }
+    }
+
+    /// The rule mirrored left to right.
+    pub fn flip_horizontal(&self) -> Self {
+        let width = self.width();
+        Self {
+            pattern: self
+                .pattern
+                .iter()
+                .map(|row| row.iter().rev().copied().collect())
+                .collect(),
+            fill: self
+                .fill
+                .iter()
+                .map(|(x, y)| (width as f64 - x, *y))
+                .collect(),
+        }
+    }
+
+    /// The rule in all four rotations, each of them also mirrored, without duplicates.
+    pub fn symmetries(&self) -> Vec<Self> {
+        let mut ret: Vec<Self> = vec![];
+        let mut rule = self.clone();
+        for _ in 0..4 {
+            for r in [rule.flip_horizontal(), rule.clone()] {
+                if !ret.contains(&r) {
+                    ret.push(r);
+                }
+            }
+            rule = rule.rotate_90();
+        }
+        ret
+    }
+
+    /// Cuts across the ends of horizontal steps one, two and three pixels long, in all their
+    /// orientations. Longer steps come first, since they take precedence.
+    pub fn defaults() -> Vec<Self> {
+        let step = |n: usize| {
+            let mut top = vec![Ignore; n + 2];
+            top[n] = Blank;
+            top[n + 1] = Blank;
+            let mut middle = vec![Smooth; n + 2];
+            middle[0] = Blank;
+            middle[n + 1] = Filled;
+            let mut bottom = vec![Filled; n + 2];
+            bottom[0] = Ignore;
+            bottom[n + 1] = Ignore;
+            let end = (n + 1) as f64;
+            Self::new(
+                vec![top, middle, bottom],
+                vec![(1., 2.), (end, 2.), (end, 1.)],
+            )
+        };
+        [3, 2, 1]
+            .into_iter()
+            .flat_map(|n| step(n).symmetries())
+            .collect()
+    }
+}
+
+impl Image {
+    /// Enlarges the image `factor` times and smooths it with `rules`, such as
+    /// `SmoothingRule::defaults()`. Rules are tried in order, and a match is skipped if one of
+    /// its `Smooth` cells has already been smoothed by an earlier rule.
+    pub fn smooth_scale(&self, factor: usize, rules: &[SmoothingRule]) -> Image {
+        let mut ret = self.resize(
+            self.width * factor,
+            self.height * factor,
+            self.x_offset,
+            self.y_offset,
+        );
+        let mut smoothed = vec![false; self.width * self.height];
+        let scale = factor as f64;
+        for rule in rules {
+            let (w, h) = (rule.width() as i64, rule.height() as i64);
+            let area = rule
+                .fill
+                .iter()
+                .zip(rule.fill.iter().cycle().skip(1))
+                .map(|(a, b)| a.0 * b.1 - b.0 * a.1)
+                .sum::<f64>();
+            for y in 1 - h..self.height as i64 {
+                for x in 1 - w..self.width as i64 {
+                    if !rule.matches(self, (x, y)) {
+                        continue;
+                    }
+                    let cells = rule
+                        .pattern
+                        .iter()
+                        .enumerate()
+                        .flat_map(|(row, tiles)| {
+                            tiles.iter().enumerate().filter_map(move |(col, tile)| {
+                                (*tile == Smooth).then_some((x + col as i64, y + row as i64))
+                            })
+                        })
+                        .map(|(cx, cy)| cy as usize * self.width + cx as usize)
+                        .collect::<Vec<_>>();
+                    if cells.iter().any(|i| smoothed[*i]) {
+                        continue;
+                    }
+                    for i in cells {
+                        smoothed[i] = true;
+                    }
+                    // Fill the pixels of the enlargement whose centers are in the polygon.
+                    let (x0, y0) = (x * factor as i64, y * factor as i64);
+                    for py in 0..h * factor as i64 {
+                        for px in 0..w * factor as i64 {
+                            let (u, v) = ((px as f64 + 0.5) / scale, (py as f64 + 0.5) / scale);
+                            let inside = rule
+                                .fill
+                                .iter()
+                                .zip(rule.fill.iter().cycle().skip(1))
+                                .all(|(a, b)| {
+                                    area * ((b.0 - a.0) * (v - a.1) - (b.1 - a.1) * (u - a.0)) >= 0.
+                                });
+                            if inside {
+                                ret.plot(x0 + px, y0 + py);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        ret
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn image(rows: &[&str]) -> Image {
+        let mut ret = Image::new(rows[0].len(), rows.len(), 0, 0);
+        for (y, row) in rows.iter().enumerate() {
+            for (x, c) in row.chars().enumerate() {
+                if c == '#' {
+                    ret.plot(x as i64, y as i64);
+                }
+            }
+        }
+        ret
+    }
+
+    #[test]
+    fn symmetries() {
+        let rule = SmoothingRule::new(
+            vec![vec![Filled, Blank, Ignore], vec![Smooth, Filled, Filled]],
+            vec![(0., 1.), (1., 1.), (0., 2.)],
+        );
+        let turned = rule.rotate_90();
+        assert_eq!((turned.width(), turned.height()), (2, 3));
+        assert_eq!(turned.pattern[0], [Smooth, Filled]);
+        assert_eq!(turned.fill, [(1., 0.), (1., 1.), (0., 0.)]);
+        assert_eq!(turned.rotate_90().rotate_90().rotate_90(), rule);
+        assert_eq!(rule.flip_horizontal().flip_horizontal(), rule);
+        assert_eq!(rule.symmetries().len(), 8);
+
+        let square = SmoothingRule::new(vec![vec![Filled; 2]; 2], vec![]);
+        assert_eq!(square.symmetries(), [square]);
+        assert_eq!(SmoothingRule::defaults().len(), 24);
+    }
+
+    #[test]
+    fn matching() {
+        let step = SmoothingRule::new(
+            vec![
+                vec![Ignore, Blank, Blank],
+                vec![Blank, Smooth, Filled],
+                vec![Ignore, Filled, Ignore],
+            ],
+            vec![(1., 2.), (2., 2.), (2., 1.)],
+        );
+        assert!(SmoothingRule::defaults().contains(&step));
+        let sprite = image(&["...", "..#", ".#."]);
+        assert!(step.matches(&sprite, (0, 0)));
+        assert!(!step.matches(&sprite, (0, 1)));
+        // A `Blank` cell doesn't match outside the image.
+        let cropped = image(&["..", ".#", "#."]);
+        assert!(!step.matches(&cropped, (-1, 0)));
+        let mut filled = sprite.clone();
+        filled.plot(1, 1);
+        assert!(!step.matches(&filled, (0, 0)));
+    }
+
+    #[test]
+    fn smooth_scale() {
+        let staircase = image(&["....", "..##", "####"]);
+        let plain = staircase.resize(16, 12, 0, 0);
+        let smoothed = staircase.smooth_scale(4, &SmoothingRule::defaults());
+        assert_eq!(staircase.smooth_scale(4, &[]).bytes, plain.bytes);
+        // Only fills in, and only around the step.
+        let added = smoothed
+            .black_pixels()
+            .into_iter()
+            .filter(|(x, y)| plain.get(*x, *y) != Some(BLACK))
+            .collect::<Vec<_>>();
+        assert!(plain
+            .black_pixels()
+            .iter()
+            .all(|(x, y)| smoothed.get(*x, *y) == Some(BLACK)));
+        assert!(!added.is_empty());
+        assert!(added.iter().all(|(_, y)| (4..8).contains(y)));
+
+        let mirrored = staircase.flip_horizontal();
+        let smoothed_mirror = mirrored.smooth_scale(4, &SmoothingRule::defaults());
+        assert_eq!(smoothed_mirror.bytes, smoothed.flip_horizontal().bytes);
+
+        let block = image(&["....", ".##.", ".##.", "...."]);
+        let scaled = block.smooth_scale(3, &SmoothingRule::defaults());
+        assert_eq!(scaled.bytes, block.resize(12, 12, 0, 0).bytes);
+    }
+}
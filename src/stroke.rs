@@ -0,0 +1,394 @@
+use crate::{Image, Point};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Cap {
+    /// The stroke ends exactly at the endpoint.
+    Butt,
+    Round,
+    /// The stroke goes on for half its width past the endpoint.
+    Square,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Join {
+    /// Falls back to `Bevel` when the miter would be longer than `miter_limit` stroke widths.
+    Miter,
+    Round,
+    Bevel,
+}
+
+/// How thick shapes are outlined. Widths of one pixel or less are drawn as Bresenham hairlines
+/// by the `plot_*` methods.
+#[derive(Clone, Copy, Debug)]
+pub struct Stroke {
+    pub width: f64,
+    pub cap: Cap,
+    pub join: Join,
+    /// Maximum ratio of the miter length to the stroke width, as in SVG.
+    pub miter_limit: f64,
+    pub color: Option<u32>,
+}
+
+impl Stroke {
+    pub fn new(width: f64) -> Self {
+        Self {
+            width,
+            cap: Cap::Butt,
+            join: Join::Miter,
+            miter_limit: 4.,
+            color: None,
+        }
+    }
+}
+
+type Vertex = (f64, f64);
+
+/// Pixel centers are sampled slightly off their integer coordinates, so that a point on the
+/// boundary of two shapes belongs to exactly one of them and a stroke of width `n` covers `n`
+/// pixels across.
+const BIAS: (f64, f64) = (1e-6, 1.7e-6);
+
+fn add((ax, ay): Vertex, (bx, by): Vertex) -> Vertex {
+    (ax + bx, ay + by)
+}
+
+fn scale((x, y): Vertex, s: f64) -> Vertex {
+    (x * s, y * s)
+}
+
+fn cross((ax, ay): Vertex, (bx, by): Vertex) -> f64 {
+    ax * by - ay * bx
+}
+
+fn unit((ax, ay): Vertex, (bx, by): Vertex) -> Vertex {
+    let (dx, dy) = (bx - ax, by - ay);
+    let len = dx.hypot(dy);
+    (dx / len, dy / len)
+}
+
+impl Image {
+    fn fill_convex(&mut self, polygon: &[Vertex], color: Option<u32>) {
+        let area = polygon
+            .iter()
+            .zip(polygon.iter().cycle().skip(1))
+            .map(|(a, b)| cross(*a, *b))
+            .sum::<f64>();
+        if area.abs() < f64::EPSILON {
+            return;
+        }
+        let orientation = area.signum();
+        let (min_x, max_x, min_y, max_y) = polygon.iter().fold(
+            (f64::MAX, f64::MIN, f64::MAX, f64::MIN),
+            |(x0, x1, y0, y1), (x, y)| (x0.min(*x), x1.max(*x), y0.min(*y), y1.max(*y)),
+        );
+        let clip = self.clip_rect();
+        let (right, bottom) = (clip.x + clip.width as i64, clip.y + clip.height as i64);
+        let x_range = (min_x.floor() as i64).max(clip.x)..=(max_x.ceil() as i64).min(right);
+        for y in (min_y.floor() as i64).max(clip.y)..=(max_y.ceil() as i64).min(bottom) {
+            for x in x_range.clone() {
+                let p = (x as f64 + BIAS.0, y as f64 + BIAS.1);
+                let inside = polygon
+                    .iter()
+                    .zip(polygon.iter().cycle().skip(1))
+                    .all(|(a, b)| {
+                        orientation * cross((b.0 - a.0, b.1 - a.1), (p.0 - a.0, p.1 - a.1)) >= 0.
+                    });
+                if inside {
+                    self.plot_color(x, y, color);
+                }
+            }
+        }
+    }
+
+    /// Fills the pixels whose centers are within `inner..outer` of `center`. Unlike polygons,
+    /// rings aren't biased, which keeps them symmetric.
+    fn fill_ring(&mut self, (cx, cy): Vertex, inner: f64, outer: f64, color: Option<u32>) {
+        let (inner2, outer2) = (inner.max(0.).powi(2), outer * outer);
+        let clip = self.clip_rect();
+        let y_max = ((cy + outer).ceil() as i64).min(clip.y + clip.height as i64);
+        let x_max = ((cx + outer).ceil() as i64).min(clip.x + clip.width as i64);
+        for y in ((cy - outer).floor() as i64).max(clip.y)..=y_max {
+            for x in ((cx - outer).floor() as i64).max(clip.x)..=x_max {
+                let (dx, dy) = (x as f64 - cx, y as f64 - cy);
+                let d2 = dx * dx + dy * dy;
+                if d2 < outer2 && (inner <= 0. || d2 >= inner2) {
+                    self.plot_color(x, y, color);
+                }
+            }
+        }
+    }
+
+    fn stroke_join(&mut self, v: Vertex, u1: Vertex, u2: Vertex, stroke: &Stroke) {
+        let h = stroke.width / 2.;
+        let turn = cross(u1, u2);
+        if turn.abs() < 1e-9 && u1.0 * u2.0 + u1.1 * u2.1 > 0. {
+            return;
+        }
+        // Normals on the outer side of the turn.
+        let side = if turn > 0. { -1. } else { 1. };
+        let o1 = (-u1.1 * side, u1.0 * side);
+        let o2 = (-u2.1 * side, u2.0 * side);
+        let bevel = [v, add(v, scale(o1, h)), add(v, scale(o2, h))];
+        match stroke.join {
+            Join::Round => self.fill_ring(v, 0., h, stroke.color),
+            Join::Bevel => self.fill_convex(&bevel, stroke.color),
+            Join::Miter => {
+                let (mx, my) = add(o1, o2);
+                let len = mx.hypot(my);
+                let cos = if len > 1e-9 {
+                    (mx * o1.0 + my * o1.1) / len
+                } else {
+                    0.
+                };
+                if cos > 1e-9 && 1. / cos <= stroke.miter_limit {
+                    let tip = add(v, scale((mx / len, my / len), h / cos));
+                    self.fill_convex(&[bevel[0], bevel[1], tip, bevel[2]], stroke.color);
+                } else {
+                    self.fill_convex(&bevel, stroke.color);
+                }
+            }
+        }
+    }
+
+    fn stroke_cap(&mut self, v: Vertex, stroke: &Stroke) {
+        if stroke.cap == Cap::Round {
+            self.fill_ring(v, 0., stroke.width / 2., stroke.color);
+        }
+    }
+
+    fn stroke_path(&mut self, points: &[Vertex], closed: bool, stroke: &Stroke) {
+        let mut points = points.to_vec();
+        points.dedup();
+        if closed && points.len() > 1 && points.first() == points.last() {
+            points.pop();
+        }
+        let h = stroke.width / 2.;
+        match points.len() {
+            0 => return,
+            1 => {
+                let (x, y) = points[0];
+                match stroke.cap {
+                    Cap::Butt => {}
+                    Cap::Round => self.fill_ring(points[0], 0., h, stroke.color),
+                    Cap::Square => self.fill_convex(
+                        &[
+                            (x - h, y - h),
+                            (x + h, y - h),
+                            (x + h, y + h),
+                            (x - h, y + h),
+                        ],
+                        stroke.color,
+                    ),
+                }
+                return;
+            }
+            _ => {}
+        }
+        let segments = if closed {
+            points.len()
+        } else {
+            points.len() - 1
+        };
+        for i in 0..segments {
+            let (a, b) = (points[i], points[(i + 1) % points.len()]);
+            let u = unit(a, b);
+            let n = (-u.1 * h, u.0 * h);
+            let square = !closed && stroke.cap == Cap::Square;
+            let a = if square && i == 0 {
+                add(a, scale(u, -h))
+            } else {
+                a
+            };
+            let b = if square && i + 1 == segments {
+                add(b, scale(u, h))
+            } else {
+                b
+            };
+            self.fill_convex(
+                &[
+                    add(a, n),
+                    add(b, n),
+                    add(b, scale(n, -1.)),
+                    add(a, scale(n, -1.)),
+                ],
+                stroke.color,
+            );
+        }
+        let joins = if closed {
+            0..points.len()
+        } else {
+            1..points.len() - 1
+        };
+        for i in joins {
+            let prev = points[(i + points.len() - 1) % points.len()];
+            let next = points[(i + 1) % points.len()];
+            let v = points[i];
+            self.stroke_join(v, unit(prev, v), unit(v, next), stroke);
+        }
+        if !closed {
+            self.stroke_cap(points[0], stroke);
+            self.stroke_cap(points[points.len() - 1], stroke);
+        }
+    }
+
+    pub fn stroke_line(&mut self, (ax, ay): Point, (bx, by): Point, stroke: &Stroke) {
+        self.stroke_path(
+            &[(ax as f64, ay as f64), (bx as f64, by as f64)],
+            false,
+            stroke,
+        );
+    }
+
+    /// Strokes the segments between consecutive `points`, and back to the first point if
+    /// `closed`.
+    pub fn stroke_polyline(&mut self, points: &[Point], closed: bool, stroke: &Stroke) {
+        let points = points
+            .iter()
+            .map(|(x, y)| (*x as f64, *y as f64))
+            .collect::<Vec<Vertex>>();
+        self.stroke_path(&points, closed, stroke);
+    }
+
+    pub fn stroke_circle(&mut self, (x, y): Point, r: f64, stroke: &Stroke) {
+        let h = stroke.width / 2.;
+        self.fill_ring((x as f64, y as f64), r - h, r + h, stroke.color);
+    }
+
+    /// Strokes the arcs of the ellipse in the selected `quadrants`, numbered as in
+    /// `Image::plot_ellipse`. The stroke lies between the ellipses with radii `a ± width / 2`
+    /// and `b ± width / 2`, which is exact for circles and close for moderate eccentricities.
+    pub fn stroke_ellipse(
+        &mut self,
+        (xm, ym): Point,
+        (a, b): (f64, f64),
+        quadrants: [bool; 4],
+        stroke: &Stroke,
+    ) {
+        let h = stroke.width / 2.;
+        let (outer_a, outer_b) = (a + h, b + h);
+        let (inner_a, inner_b) = (a - h, b - h);
+        if outer_a <= 0. || outer_b <= 0. {
+            return;
+        }
+        let clip = self.clip_rect();
+        let y_max = ((ym as f64 + outer_b).ceil() as i64).min(clip.y + clip.height as i64);
+        let x_max = ((xm as f64 + outer_a).ceil() as i64).min(clip.x + clip.width as i64);
+        for y in ((ym as f64 - outer_b).floor() as i64).max(clip.y)..=y_max {
+            for x in ((xm as f64 - outer_a).floor() as i64).max(clip.x)..=x_max {
+                let (dx, dy) = ((x - xm) as f64, (y - ym) as f64);
+                let in_quadrant = (quadrants[0] && dx >= 0. && dy >= 0.)
+                    || (quadrants[1] && dx <= 0. && dy >= 0.)
+                    || (quadrants[2] && dx <= 0. && dy <= 0.)
+                    || (quadrants[3] && dx >= 0. && dy <= 0.);
+                let inside_outer = (dx / outer_a).powi(2) + (dy / outer_b).powi(2) < 1.;
+                let outside_inner = inner_a <= 0.
+                    || inner_b <= 0.
+                    || (dx / inner_a).powi(2) + (dy / inner_b).powi(2) >= 1.;
+                if in_quadrant && inside_outer && outside_inner {
+                    self.plot_color(x, y, stroke.color);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BLACK;
+
+    fn black(image: &Image) -> Vec<Point> {
+        let mut ret = vec![];
+        for y in 0..image.height as i64 {
+            for x in 0..image.width as i64 {
+                if image.get(x, y) == Some(BLACK) {
+                    ret.push((x, y));
+                }
+            }
+        }
+        ret
+    }
+
+    fn stroked(cap: Cap) -> Vec<Point> {
+        let mut image = Image::new(20, 12, 0, 0);
+        let stroke = Stroke {
+            cap,
+            ..Stroke::new(4.)
+        };
+        image.stroke_line((4, 5), (14, 5), &stroke);
+        black(&image)
+    }
+
+    #[test]
+    fn caps() {
+        // A stroke of width 4 covers 4 rows, and butt caps end it at the endpoints.
+        let butt = stroked(Cap::Butt);
+        assert_eq!(butt.len(), 4 * 10);
+        assert!(butt
+            .iter()
+            .all(|(x, y)| (4..14).contains(x) && (3..7).contains(y)));
+        // Square caps go on for half the width.
+        let square = stroked(Cap::Square);
+        assert_eq!(square.len(), 4 * 14);
+        assert!(square.iter().all(|(x, _)| (2..16).contains(x)));
+        // Round caps lie in between.
+        let round = stroked(Cap::Round);
+        assert!(butt.iter().all(|p| round.contains(p)));
+        assert!(round.iter().all(|p| square.contains(p)));
+        assert!(round.len() > butt.len() && round.len() < square.len());
+    }
+
+    #[test]
+    fn joins() {
+        let corner = |join: Join, miter_limit: f64| {
+            let mut image = Image::new(24, 24, 0, 0);
+            let stroke = Stroke {
+                join,
+                miter_limit,
+                ..Stroke::new(6.)
+            };
+            image.stroke_polyline(&[(4, 12), (12, 12), (12, 20)], false, &stroke);
+            black(&image)
+        };
+        let (miter, round, bevel) = (
+            corner(Join::Miter, 4.),
+            corner(Join::Round, 4.),
+            corner(Join::Bevel, 4.),
+        );
+        // The outer corner of the turn is only filled by the miter.
+        assert!(miter.contains(&(14, 9)));
+        assert!(!round.contains(&(14, 9)) && !bevel.contains(&(14, 9)));
+        // The round join bulges out past the bevel.
+        assert!(round.contains(&(14, 10)) && !bevel.contains(&(14, 10)));
+        assert!(round.iter().chain(&bevel).all(|p| miter.contains(p)));
+        // A right angle's miter is √2 stroke widths long.
+        assert_eq!(corner(Join::Miter, 1.4), bevel);
+    }
+
+    #[test]
+    fn ring() {
+        let mut image = Image::new(21, 21, 0, 0);
+        image.stroke_circle((10, 10), 6., &Stroke::new(2.));
+        let pixels = black(&image);
+        assert!(!pixels.is_empty());
+        for (x, y) in pixels {
+            let d = ((x - 10) as f64).hypot((y - 10) as f64);
+            assert!((5. ..7.).contains(&d), "{} {}", x, y);
+        }
+    }
+
+    #[test]
+    fn thick_lines_connect() {
+        // Lines drawn end to end with `plot_line_width` leave no gap at the shared point.
+        let mut image = Image::new(20, 20, 0, 0);
+        image.plot_line_width((2, 2), (10, 10), 3.);
+        image.plot_line_width((10, 10), (17, 3), 3.);
+        for (dx, dy) in [(-1, 0), (1, 0), (0, -1), (0, 1)] {
+            assert_eq!(image.get(10 + dx, 10 + dy), Some(BLACK));
+        }
+        // Hairlines are single Bresenham steps.
+        let mut image = Image::new(20, 20, 0, 0);
+        image.plot_line_width((2, 3), (17, 8), 1.);
+        assert_eq!(black(&image).len(), 16);
+    }
+}
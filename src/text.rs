@@ -0,0 +1,343 @@
+use crate::{BitmapFont, Image, Point};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Alignment {
+    Left,
+    Center,
+    Right,
+    /// Stretches the gaps between words so that every line but the last of each paragraph
+    /// spans the whole box.
+    Justify,
+}
+
+/// A rectangle to lay text out in. Text wraps at `width` and is clipped to `width` by `height`
+/// when drawn.
+#[derive(Clone, Copy, Debug)]
+pub struct TextBox {
+    pub x: i64,
+    pub y: i64,
+    pub width: usize,
+    pub height: usize,
+    pub alignment: Alignment,
+    /// Tab stops are `tab_size` space advances apart.
+    pub tab_size: usize,
+    /// Extra pixels between consecutive baselines.
+    pub line_spacing: usize,
+}
+
+/// A laid out line: glyph indices with their pen positions relative to the box's left edge.
+#[derive(Clone, Debug, Default)]
+pub struct TextLine {
+    pub glyphs: Vec<(usize, i64)>,
+    pub width: i64,
+    pub baseline: i64,
+}
+
+enum Token {
+    Word(Vec<(usize, i64)>),
+    Space,
+    Tab,
+}
+
+/// Line being filled. `gaps` holds the glyph positions preceded by inter-word whitespace.
+#[derive(Default)]
+struct PendingLine {
+    glyphs: Vec<(usize, i64)>,
+    gaps: Vec<usize>,
+    pen: i64,
+}
+
+impl BitmapFont {
+    /// The glyph drawn for characters the font doesn't have: the font's `default_char`, U+FFFD
+    /// or `?`, whichever exists first.
+    pub fn replacement_index(&self) -> Option<usize> {
+        self.default_char
+            .and_then(|c| self.glyph_index(c))
+            .or_else(|| self.glyph_index(char::REPLACEMENT_CHARACTER))
+            .or_else(|| self.glyph_index('?'))
+    }
+
+    pub fn glyph_index_or_replacement(&self, c: char) -> Option<usize> {
+        self.glyph_index(c).or_else(|| self.replacement_index())
+    }
+
+    pub fn line_height(&self) -> usize {
+        self.ascent + self.descent
+    }
+
+    /// Width of `s` on a single line, as `Image::write_str` would draw it.
+    pub fn measure_str(&self, s: &str) -> i64 {
+        s.chars()
+            .filter_map(|c| self.glyph_index_or_replacement(c))
+            .map(|idx| self.glyph_metrics(idx).advance)
+            .sum()
+    }
+}
+
+impl TextBox {
+    pub fn new((x, y): Point, width: usize, height: usize) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+            alignment: Alignment::Left,
+            tab_size: 4,
+            line_spacing: 0,
+        }
+    }
+
+    fn tokenize(font: &BitmapFont, paragraph: &str) -> Vec<Token> {
+        let mut ret = vec![];
+        let mut word = vec![];
+        for c in paragraph.chars() {
+            if c == ' ' || c == '\t' {
+                if !word.is_empty() {
+                    ret.push(Token::Word(std::mem::take(&mut word)));
+                }
+                ret.push(if c == ' ' { Token::Space } else { Token::Tab });
+            } else if let Some(idx) = font.glyph_index_or_replacement(c) {
+                word.push((idx, font.glyph_metrics(idx).advance));
+            }
+        }
+        if !word.is_empty() {
+            ret.push(Token::Word(word));
+        }
+        ret
+    }
+
+    /// Breaks `s` into lines at `\n` and wherever the next word would overflow the box. Words
+    /// wider than the box are broken between characters.
+    pub fn layout(&self, font: &BitmapFont, s: &str) -> Vec<TextLine> {
+        let max_width = self.width as i64;
+        let space = font
+            .glyph_index_or_replacement(' ')
+            .map(|idx| font.glyph_metrics(idx).advance)
+            .unwrap_or(font.glyph_width as i64);
+        let tab_stop = (space * self.tab_size as i64).max(1);
+
+        let mut lines: Vec<(PendingLine, bool)> = vec![];
+        for paragraph in s.split('\n') {
+            let mut line = PendingLine::default();
+            let mut whitespace = vec![];
+            for token in Self::tokenize(font, paragraph) {
+                let word = match token {
+                    Token::Word(word) => word,
+                    ws => {
+                        whitespace.push(ws);
+                        continue;
+                    }
+                };
+                // Whitespace is only kept at the start of a paragraph and between words.
+                let first_line = lines.last().is_none_or(|(_, last)| *last);
+                let mut pen = line.pen;
+                if !line.glyphs.is_empty() || first_line {
+                    for ws in whitespace.drain(..) {
+                        pen = match ws {
+                            Token::Tab => (pen / tab_stop + 1) * tab_stop,
+                            _ => pen + space,
+                        };
+                    }
+                }
+                whitespace.clear();
+                let word_width = word.iter().map(|(_, advance)| advance).sum::<i64>();
+                if !line.glyphs.is_empty() && pen + word_width > max_width {
+                    lines.push((std::mem::take(&mut line), false));
+                    pen = 0;
+                } else if !line.glyphs.is_empty() && pen != line.pen {
+                    line.gaps.push(line.glyphs.len());
+                }
+                line.pen = pen;
+                for (idx, advance) in word {
+                    if !line.glyphs.is_empty()
+                        && line.pen + advance > max_width
+                        && word_width > max_width
+                    {
+                        lines.push((std::mem::take(&mut line), false));
+                    }
+                    line.glyphs.push((idx, line.pen));
+                    line.pen += advance;
+                }
+            }
+            lines.push((line, true));
+        }
+
+        let line_height = (font.line_height() + self.line_spacing) as i64;
+        lines
+            .into_iter()
+            .enumerate()
+            .map(|(i, (line, last_in_paragraph))| {
+                let justify = self.alignment == Alignment::Justify
+                    && !last_in_paragraph
+                    && !line.gaps.is_empty();
+                let extra = (max_width - line.pen).max(0);
+                let mut glyphs = line.glyphs;
+                match self.alignment {
+                    Alignment::Center => glyphs.iter_mut().for_each(|g| g.1 += extra / 2),
+                    Alignment::Right => glyphs.iter_mut().for_each(|g| g.1 += extra),
+                    _ if justify => {
+                        // The first `extra % gaps` gaps get one pixel more than the rest.
+                        let gaps = line.gaps.len() as i64;
+                        for (n, start) in line.gaps.iter().enumerate() {
+                            let shift =
+                                extra / gaps + if (n as i64) < extra % gaps { 1 } else { 0 };
+                            glyphs[*start..].iter_mut().for_each(|g| g.1 += shift);
+                        }
+                    }
+                    _ => {}
+                }
+                TextLine {
+                    width: if justify { max_width } else { line.pen },
+                    glyphs,
+                    baseline: font.ascent as i64 + i as i64 * line_height,
+                }
+            })
+            .collect()
+    }
+
+    /// Size of the laid out text, which can be taller than the box.
+    pub fn measure(&self, font: &BitmapFont, s: &str) -> (usize, usize) {
+        let lines = self.layout(font, s);
+        let width = lines.iter().map(|l| l.width).max().unwrap_or(0);
+        let line_height = font.line_height() + self.line_spacing;
+        (
+            width as usize,
+            (lines.len() * line_height).saturating_sub(self.line_spacing),
+        )
+    }
+}
+
+impl Image {
+    /// Draws `s` laid out by `text_box`, clipped to the box.
+    pub fn write_text(&mut self, font: &BitmapFont, s: &str, text_box: &TextBox) {
+        let (left, top) = (text_box.x, text_box.y);
+        let right = left + text_box.width as i64;
+        let bottom = top + text_box.height as i64;
+        for line in text_box.layout(font, s) {
+            for (idx, pen) in line.glyphs {
+                let metrics = font.glyph_metrics(idx);
+                let glyph = match font.glyph_at(idx) {
+                    Some(g) => g,
+                    None => continue,
+                };
+                let x = left + pen + metrics.x_offset;
+                let y = top + line.baseline - metrics.y_offset - metrics.height as i64;
+                let (x0, y0) = (x.max(left).max(0), y.max(top).max(0));
+                let x1 = (x + metrics.width as i64).min(right);
+                let y1 = (y + metrics.height as i64).min(bottom);
+                if x1 <= x0 || y1 <= y0 {
+                    continue;
+                }
+                self.copy(
+                    &glyph,
+                    (x0 as usize, y0 as usize),
+                    ((x0 - x) as usize, (y0 - y) as usize),
+                    (x1 - x0) as usize,
+                    (y1 - y0) as usize,
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A monospace font of 4 × 6 cells indexed by code point.
+    fn font() -> BitmapFont {
+        BitmapFont::new(Image::new(256 * 4, 6, 0, 0), (4, 6), 0, 0)
+    }
+
+    fn layout(text_box: &TextBox, s: &str) -> Vec<Vec<(char, i64)>> {
+        text_box
+            .layout(&font(), s)
+            .into_iter()
+            .map(|line| {
+                line.glyphs
+                    .into_iter()
+                    .map(|(idx, pen)| (idx as u8 as char, pen))
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn wrap() {
+        let text_box = TextBox::new((0, 0), 20, 100);
+        assert_eq!(
+            layout(&text_box, "ab cd ef\ng"),
+            [
+                vec![('a', 0), ('b', 4), ('c', 12), ('d', 16)],
+                vec![('e', 0), ('f', 4)],
+                vec![('g', 0)],
+            ]
+        );
+        let lines = text_box.layout(&font(), "ab cd ef");
+        assert_eq!(
+            lines
+                .iter()
+                .map(|l| (l.width, l.baseline))
+                .collect::<Vec<_>>(),
+            [(20, 6), (8, 12)]
+        );
+        assert_eq!(text_box.measure(&font(), "ab cd ef"), (20, 12));
+    }
+
+    #[test]
+    fn long_words_break_between_characters() {
+        let text_box = TextBox::new((0, 0), 8, 100);
+        assert_eq!(
+            layout(&text_box, "abcde"),
+            [
+                vec![('a', 0), ('b', 4)],
+                vec![('c', 0), ('d', 4)],
+                vec![('e', 0)],
+            ]
+        );
+    }
+
+    #[test]
+    fn alignment() {
+        let mut text_box = TextBox::new((0, 0), 20, 100);
+        text_box.alignment = Alignment::Right;
+        assert_eq!(layout(&text_box, "ab"), [vec![('a', 12), ('b', 16)]]);
+        text_box.alignment = Alignment::Center;
+        assert_eq!(layout(&text_box, "ab"), [vec![('a', 6), ('b', 10)]]);
+    }
+
+    #[test]
+    fn justify() {
+        let mut text_box = TextBox::new((0, 0), 26, 100);
+        text_box.alignment = Alignment::Justify;
+        let lines = text_box.layout(&font(), "a b c de");
+        // 6 pixels are shared by the two gaps of the first line, and the last line of the
+        // paragraph isn't stretched.
+        assert_eq!(
+            layout(&text_box, "a b c de"),
+            [
+                vec![('a', 0), ('b', 11), ('c', 22)],
+                vec![('d', 0), ('e', 4)]
+            ]
+        );
+        assert_eq!((lines[0].width, lines[1].width), (26, 8));
+        text_box.width = 27;
+        assert_eq!(
+            layout(&text_box, "a b c de")[0],
+            [('a', 0), ('b', 12), ('c', 23)]
+        );
+    }
+
+    #[test]
+    fn tabs() {
+        let mut text_box = TextBox::new((0, 0), 100, 100);
+        text_box.tab_size = 2;
+        assert_eq!(layout(&text_box, "a\tb"), [vec![('a', 0), ('b', 8)]]);
+        assert_eq!(
+            layout(&text_box, "abc\td"),
+            [vec![('a', 0), ('b', 4), ('c', 8), ('d', 16)]]
+        );
+        assert_eq!(layout(&text_box, "\tx"), [vec![('x', 8)]]);
+        assert_eq!(layout(&text_box, "ab\t\tc")[0][2], ('c', 24));
+    }
+}
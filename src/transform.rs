@@ -0,0 +1,335 @@
+//! Affine transformations of the plane and resampling of images through them.
+
+use std::collections::HashMap;
+use std::ops::Mul;
+
+use crate::{from_u32_rgb, from_u8_rgb, Image, Line, Rect, WHITE};
+
+/// The map `(x, y) ↦ (a * x + b * y + c, d * x + e * y + f)`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Affine {
+    pub a: f64,
+    pub b: f64,
+    pub c: f64,
+    pub d: f64,
+    pub e: f64,
+    pub f: f64,
+}
+
+impl Default for Affine {
+    fn default() -> Self {
+        Self::identity()
+    }
+}
+
+impl Affine {
+    pub const fn identity() -> Self {
+        Self::new(1., 0., 0., 0., 1., 0.)
+    }
+
+    pub const fn new(a: f64, b: f64, c: f64, d: f64, e: f64, f: f64) -> Self {
+        Self { a, b, c, d, e, f }
+    }
+
+    pub const fn translate(tx: f64, ty: f64) -> Self {
+        Self::new(1., 0., tx, 0., 1., ty)
+    }
+
+    pub const fn scale(sx: f64, sy: f64) -> Self {
+        Self::new(sx, 0., 0., 0., sy, 0.)
+    }
+
+    /// Rotation by `angle` radians about the origin, clockwise on screen where y grows
+    /// downwards.
+    pub fn rotate(angle: f64) -> Self {
+        let (s, c) = angle.sin_cos();
+        Self::new(c, -s, 0., s, c, 0.)
+    }
+
+    /// Rotation by `angle` radians about `(cx, cy)`.
+    pub fn rotate_about(angle: f64, (cx, cy): (f64, f64)) -> Self {
+        Self::translate(cx, cy) * Self::rotate(angle) * Self::translate(-cx, -cy)
+    }
+
+    /// Shifts x by `kx * y` and y by `ky * x`.
+    pub const fn shear(kx: f64, ky: f64) -> Self {
+        Self::new(1., kx, 0., ky, 1., 0.)
+    }
+
+    /// Reflection across the implicit line `a * x + b * y + c = 0`, `None` if it's degenerate.
+    pub fn reflect((a, b, c): Line) -> Option<Self> {
+        let (a, b, c) = (a as f64, b as f64, c as f64);
+        let d = a * a + b * b;
+        if d == 0. {
+            return None;
+        }
+        Some(Self::new(
+            1. - 2. * a * a / d,
+            -2. * a * b / d,
+            -2. * a * c / d,
+            -2. * a * b / d,
+            1. - 2. * b * b / d,
+            -2. * b * c / d,
+        ))
+    }
+
+    pub fn determinant(&self) -> f64 {
+        self.a * self.e - self.b * self.d
+    }
+
+    /// The transformation that applies `self` and then `next`, i.e. `next * self`.
+    pub fn then(self, next: Self) -> Self {
+        next * self
+    }
+
+    /// The inverse transformation, `None` if `self` collapses the plane onto a line or point.
+    pub fn invert(&self) -> Option<Self> {
+        let det = self.determinant();
+        if det == 0. || !det.is_finite() {
+            return None;
+        }
+        let (a, b, d, e) = (self.e / det, -self.b / det, -self.d / det, self.a / det);
+        Some(Self::new(
+            a,
+            b,
+            -(a * self.c + b * self.f),
+            d,
+            e,
+            -(d * self.c + e * self.f),
+        ))
+    }
+
+    pub fn apply(&self, (x, y): (f64, f64)) -> (f64, f64) {
+        (
+            self.a * x + self.b * y + self.c,
+            self.d * x + self.e * y + self.f,
+        )
+    }
+}
+
+/// Composition: `(m * n).apply(p)` is `m.apply(n.apply(p))`.
+impl Mul for Affine {
+    type Output = Self;
+    fn mul(self, n: Self) -> Self {
+        Self::new(
+            self.a * n.a + self.b * n.d,
+            self.a * n.b + self.b * n.e,
+            self.a * n.c + self.b * n.f + self.c,
+            self.d * n.a + self.e * n.d,
+            self.d * n.b + self.e * n.e,
+            self.d * n.c + self.e * n.f + self.f,
+        )
+    }
+}
+
+/// How `Image::warp` picks the color of a destination pixel from the source.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Sampling {
+    /// The source pixel under the destination pixel's center.
+    Nearest,
+    /// The four source pixels around the center, blended by distance. Produces grays from a
+    /// black and white image.
+    Bilinear,
+    /// The most common of the source pixels under a 3×3 grid within the destination pixel,
+    /// which keeps thin strokes of downscaled bitmaps from breaking up.
+    Majority,
+}
+
+impl Image {
+    fn sample_nearest(&self, (x, y): (f64, f64)) -> u32 {
+        self.get(x.round() as i64, y.round() as i64)
+            .unwrap_or(WHITE)
+    }
+
+    fn sample_bilinear(&self, (x, y): (f64, f64)) -> u32 {
+        let (x0, y0) = (x.floor(), y.floor());
+        let (fx, fy) = (x - x0, y - y0);
+        let (x0, y0) = (x0 as i64, y0 as i64);
+        let mut sum = [0.; 3];
+        for (dx, dy, w) in [
+            (0, 0, (1. - fx) * (1. - fy)),
+            (1, 0, fx * (1. - fy)),
+            (0, 1, (1. - fx) * fy),
+            (1, 1, fx * fy),
+        ] {
+            let (r, g, b) = from_u32_rgb(self.get(x0 + dx, y0 + dy).unwrap_or(WHITE));
+            sum[0] += w * r as f64;
+            sum[1] += w * g as f64;
+            sum[2] += w * b as f64;
+        }
+        from_u8_rgb(
+            sum[0].round() as u8,
+            sum[1].round() as u8,
+            sum[2].round() as u8,
+        )
+    }
+
+    /// The pixels covered by this image after `transform`, which may lie at negative
+    /// coordinates.
+    pub fn warp_bounds(&self, transform: &Affine) -> Rect {
+        // The outer edges of the border pixels, since pixel centers are at integer coordinates.
+        let (w, h) = (self.width as f64 - 0.5, self.height as f64 - 0.5);
+        let (min_x, min_y, max_x, max_y) = [(-0.5, -0.5), (w, -0.5), (w, h), (-0.5, h)]
+            .into_iter()
+            .map(|p| transform.apply(p))
+            .fold(
+                (f64::MAX, f64::MAX, f64::MIN, f64::MIN),
+                |(x0, y0, x1, y1), (x, y)| (x0.min(x), y0.min(y), x1.max(x), y1.max(y)),
+            );
+        // Pixels whose centers are inside, leaving out the right and bottom edges like fills
+        // do. The tolerance keeps rounding errors from adding a row or column.
+        let start = |v: f64| (v - 1e-9).ceil() as i64;
+        let (x, y) = (start(min_x), start(min_y));
+        Rect {
+            x,
+            y,
+            width: (start(max_x) - x).max(0) as usize,
+            height: (start(max_y) - y).max(0) as usize,
+        }
+    }
+
+    /// Applies `transform` by inverse-mapping each destination pixel into this image. The
+    /// result is just large enough to hold the transformed image, with its top left pixel at
+    /// the position given by `warp_bounds`. Returns an empty image if `transform` isn't
+    /// invertible.
+    pub fn warp(&self, transform: &Affine, sampling: Sampling) -> Image {
+        let inverse = match transform.invert() {
+            Some(inverse) => inverse,
+            None => return Image::new(0, 0, self.x_offset, self.y_offset),
+        };
+        let bounds = self.warp_bounds(transform);
+        let mut ret = Image::new(bounds.width, bounds.height, self.x_offset, self.y_offset);
+        let offsets = [-1. / 3., 0., 1. / 3.];
+        let mut votes: HashMap<u32, usize> = HashMap::new();
+        for y in 0..bounds.height {
+            for x in 0..bounds.width {
+                let (dx, dy) = ((bounds.x + x as i64) as f64, (bounds.y + y as i64) as f64);
+                let color = match sampling {
+                    Sampling::Nearest => self.sample_nearest(inverse.apply((dx, dy))),
+                    Sampling::Bilinear => self.sample_bilinear(inverse.apply((dx, dy))),
+                    Sampling::Majority => {
+                        votes.clear();
+                        for oy in offsets {
+                            for ox in offsets {
+                                let p = inverse.apply((dx + ox, dy + oy));
+                                *votes.entry(self.sample_nearest(p)).or_default() += 1;
+                            }
+                        }
+                        // Ties go to the sample under the center.
+                        let center = self.sample_nearest(inverse.apply((dx, dy)));
+                        votes
+                            .iter()
+                            .max_by_key(|(c, n)| (**n, **c == center))
+                            .map_or(center, |(c, _)| *c)
+                    }
+                };
+                ret.bytes[y * bounds.width + x] = color;
+            }
+        }
+        ret.hotspot = self.hotspot.and_then(|(hx, hy)| {
+            let (x, y) = transform.apply((hx as f64, hy as f64));
+            let (x, y) = (x.round() as i64 - bounds.x, y.round() as i64 - bounds.y);
+            if x < 0 || y < 0 || x >= bounds.width as i64 || y >= bounds.height as i64 {
+                return None;
+            }
+            Some((x as usize, y as usize))
+        });
+        ret
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BLACK;
+    use std::f64::consts::FRAC_PI_2;
+
+    fn close((x1, y1): (f64, f64), (x2, y2): (f64, f64)) -> bool {
+        (x1 - x2).abs() < 1e-9 && (y1 - y2).abs() < 1e-9
+    }
+
+    #[test]
+    fn composition() {
+        let m = Affine::translate(3., -2.) * Affine::scale(2., 0.5);
+        assert!(close(m.apply((1., 4.)), (5., 0.)));
+        assert_eq!(Affine::scale(2., 0.5).then(Affine::translate(3., -2.)), m);
+        // Clockwise on screen: the x axis turns towards positive y.
+        assert!(close(Affine::rotate(FRAC_PI_2).apply((1., 0.)), (0., 1.)));
+        let r = Affine::rotate_about(FRAC_PI_2, (5., 5.));
+        assert!(close(r.apply((5., 5.)), (5., 5.)));
+        assert!(close(r.apply((6., 5.)), (5., 6.)));
+        assert!(close(Affine::shear(0.5, 0.).apply((2., 4.)), (4., 4.)));
+
+        let diagonal = Affine::reflect((1, -1, 0)).unwrap();
+        assert!(close(diagonal.apply((3., 1.)), (1., 3.)));
+        let vertical = Affine::reflect((1, 0, -4)).unwrap();
+        assert!(close(vertical.apply((1., 7.)), (7., 7.)));
+        assert_eq!(vertical.determinant(), -1.);
+        assert_eq!(Affine::reflect((0, 0, 1)), None);
+    }
+
+    #[test]
+    fn inverse() {
+        let m =
+            Affine::rotate_about(0.7, (3., 1.)) * Affine::shear(0.3, -0.2) * Affine::scale(2., 3.);
+        let inverse = m.invert().unwrap();
+        for p in [(0., 0.), (1., -4.), (12.5, 7.)] {
+            assert!(close(inverse.apply(m.apply(p)), p));
+            assert!(close(m.apply(inverse.apply(p)), p));
+        }
+        assert_eq!(Affine::scale(1., 0.).invert(), None);
+        assert_eq!(Affine::shear(1., 1.).invert(), None);
+    }
+
+    #[test]
+    fn warp() {
+        let mut image = Image::new(5, 3, 0, 0);
+        image.plot(0, 0);
+        image.plot(4, 1);
+        image.plot(2, 2);
+        image.hotspot = Some((4, 1));
+
+        let same = image.warp(&Affine::identity(), Sampling::Nearest);
+        assert_eq!((same.width, same.height), (5, 3));
+        assert_eq!(same.bytes, image.bytes);
+
+        let shifted = Affine::translate(-7., 4.);
+        assert_eq!(
+            image.warp_bounds(&shifted),
+            Rect {
+                x: -7,
+                y: 4,
+                width: 5,
+                height: 3
+            }
+        );
+        assert_eq!(image.warp(&shifted, Sampling::Majority).bytes, image.bytes);
+
+        // Each source pixel covers a 3×3 block, centered on its scaled center.
+        let tripled = image.warp(&Affine::scale(3., 3.), Sampling::Nearest);
+        assert_eq!((tripled.width, tripled.height), (15, 9));
+        assert_eq!(tripled.black_pixels().len(), 27);
+        assert_eq!(tripled.get(0, 0), Some(BLACK));
+        assert_eq!(tripled.get(3, 0), Some(WHITE));
+        assert_eq!(tripled.hotspot, Some((13, 4)));
+
+        // A quarter turn clockwise about the origin puts the image left of the y axis.
+        let turn = Affine::rotate(FRAC_PI_2);
+        let turned = image.warp(&turn, Sampling::Nearest);
+        assert_eq!(image.warp_bounds(&turn).x, -2);
+        assert_eq!((turned.width, turned.height), (3, 5));
+        assert_eq!(turned.get(2, 0), Some(BLACK));
+        assert_eq!(turned.get(1, 4), Some(BLACK));
+        assert_eq!(turned.get(0, 2), Some(BLACK));
+        assert_eq!(turned.black_pixels().len(), 3);
+
+        // Halfway between a black and a white pixel.
+        let gray = image.warp(&Affine::translate(0.5, 0.), Sampling::Bilinear);
+        assert_eq!(gray.get(0, 0), Some(from_u8_rgb(128, 128, 128)));
+
+        assert_eq!(
+            image.warp(&Affine::scale(0., 1.), Sampling::Nearest).width,
+            0
+        );
+    }
+}
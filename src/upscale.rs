@@ -0,0 +1,354 @@
+//! Edge-aware integer upscalers for pixel art. Unlike `Image::resize`, they look at each
+//! pixel's neighbors to round off the staircase of diagonal edges.
+//!
+//! Neighbors are named after the pixel `E` being scaled:
+//!
+//! ```text
+//! A B C
+//! D E F
+//! G H I
+//! ```
+
+use crate::{from_u32_rgb, from_u8_rgb, Image};
+
+/// The Y, U and V components that colors are compared by, as in Maxim Stepin's hqx.
+fn yuv(c: u32) -> (f64, f64, f64) {
+    let (r, g, b) = from_u32_rgb(c);
+    let (r, g, b) = (r as f64, g as f64, b as f64);
+    let y = 0.299 * r + 0.587 * g + 0.114 * b;
+    (y, (b - y) * 0.492, (r - y) * 0.877)
+}
+
+/// Whether two colors are close enough to be treated as the same area.
+fn similar(a: u32, b: u32) -> bool {
+    let ((ya, ua, va), (yb, ub, vb)) = (yuv(a), yuv(b));
+    (ya - yb).abs() <= 48. && (ua - ub).abs() <= 7. && (va - vb).abs() <= 6.
+}
+
+/// The color distance used by xBR.
+fn distance(a: u32, b: u32) -> f64 {
+    let ((ya, ua, va), (yb, ub, vb)) = (yuv(a), yuv(b));
+    48. * (ya - yb).abs() + 7. * (ua - ub).abs() + 6. * (va - vb).abs()
+}
+
+/// The weighted average of `colors`.
+fn mix(colors: &[(u32, u32)]) -> u32 {
+    let total = colors.iter().map(|(_, w)| *w).sum::<u32>() as f64;
+    let mut sum = [0.; 3];
+    for (c, w) in colors {
+        let (r, g, b) = from_u32_rgb(*c);
+        sum[0] += (r as u32 * w) as f64;
+        sum[1] += (g as u32 * w) as f64;
+        sum[2] += (b as u32 * w) as f64;
+    }
+    from_u8_rgb(
+        (sum[0] / total).round() as u8,
+        (sum[1] / total).round() as u8,
+        (sum[2] / total).round() as u8,
+    )
+}
+
+impl Image {
+    /// The pixel at `(x, y)`, with the image's border repeated outwards.
+    fn clamped(&self, x: i64, y: i64) -> u32 {
+        let x = x.clamp(0, self.width as i64 - 1) as usize;
+        let y = y.clamp(0, self.height as i64 - 1) as usize;
+        self.bytes[y * self.width + x]
+    }
+
+    /// Replaces every pixel by the `factor × factor` block that `block` writes, row by row. A
+    /// `factor` of 0 gives an empty image.
+    fn upscale(&self, factor: usize, block: impl Fn(i64, i64, &mut [u32])) -> Image {
+        if factor == 0 {
+            return Image::new(0, 0, self.x_offset, self.y_offset);
+        }
+        let width = self.width * factor;
+        let mut ret = Image::new(width, self.height * factor, self.x_offset, self.y_offset);
+        let mut pixels = vec![0; factor * factor];
+        for y in 0..self.height {
+            for x in 0..self.width {
+                block(x as i64, y as i64, &mut pixels);
+                for (i, row) in pixels.chunks(factor).enumerate() {
+                    let start = (y * factor + i) * width + x * factor;
+                    ret.bytes[start..start + factor].copy_from_slice(row);
+                }
+            }
+        }
+        ret.hotspot = self
+            .hotspot
+            .map(|(x, y)| (x * factor + factor / 2, y * factor + factor / 2));
+        ret
+    }
+
+    /// EPX, known as Scale2x: a corner takes the color of its two neighbors when they agree
+    /// and the opposite ones don't. Keeps the image's colors and never blurs.
+    pub fn scale2x(&self) -> Image {
+        self.upscale(2, |x, y, out| {
+            let p = |dx, dy| self.clamped(x + dx, y + dy);
+            let (b, d, e, f, h) = (p(0, -1), p(-1, 0), p(0, 0), p(1, 0), p(0, 1));
+            if b != h && d != f {
+                out[0] = if d == b { d } else { e };
+                out[1] = if b == f { f } else { e };
+                out[2] = if d == h { d } else { e };
+                out[3] = if h == f { f } else { e };
+            } else {
+                out.fill(e);
+            }
+        })
+    }
+
+    /// Scale3x, the extension of Scale2x to a factor of three.
+    pub fn scale3x(&self) -> Image {
+        self.upscale(3, |x, y, out| {
+            let p = |dx, dy| self.clamped(x + dx, y + dy);
+            let (a, b, c) = (p(-1, -1), p(0, -1), p(1, -1));
+            let (d, e, f) = (p(-1, 0), p(0, 0), p(1, 0));
+            let (g, h, i) = (p(-1, 1), p(0, 1), p(1, 1));
+            out.fill(e);
+            if b == h || d == f {
+                return;
+            }
+            if d == b {
+                out[0] = d;
+            }
+            if (d == b && e != c) || (b == f && e != a) {
+                out[1] = b;
+            }
+            if b == f {
+                out[2] = f;
+            }
+            if (d == b && e != g) || (d == h && e != a) {
+                out[3] = d;
+            }
+            if (b == f && e != i) || (h == f && e != c) {
+                out[5] = f;
+            }
+            if d == h {
+                out[6] = d;
+            }
+            if (d == h && e != i) || (h == f && e != g) {
+                out[7] = h;
+            }
+            if h == f {
+                out[8] = f;
+            }
+        })
+    }
+
+    /// Scale2x applied twice.
+    pub fn scale4x(&self) -> Image {
+        self.scale2x().scale2x()
+    }
+
+    /// Blends the corners of each pixel with the neighbors around them, at a factor of 2 or 3.
+    fn smooth(&self, factor: usize) -> Image {
+        self.upscale(factor, |x, y, out| {
+            let p = |dx, dy| self.clamped(x + dx, y + dy);
+            let e = p(0, 0);
+            // The blend for the corner towards (sx, sy), from the vertical neighbor `v`,
+            // the horizontal one `w` and the diagonal one `c`.
+            let corner = |sx: i64, sy: i64| {
+                let (v, w, c) = (p(0, sy), p(sx, 0), p(sx, sy));
+                if similar(e, v) || similar(e, w) || !similar(v, w) {
+                    return e;
+                }
+                // An edge cuts off the corner, more so when the diagonal is on its side.
+                if similar(c, v) {
+                    mix(&[(e, 2), (v, 3), (w, 3)])
+                } else {
+                    mix(&[(e, 2), (v, 1), (w, 1)])
+                }
+            };
+            // The blend for the middle of the side towards `n`, when the edge cutting one of
+            // the corners next to it continues along it.
+            let side = |n: u32, m1: u32, m2: u32| {
+                if !similar(e, n) && (similar(n, m1) || similar(n, m2)) {
+                    mix(&[(e, 7), (n, 1)])
+                } else {
+                    e
+                }
+            };
+            let (tl, tr, bl, br) = (corner(-1, -1), corner(1, -1), corner(-1, 1), corner(1, 1));
+            if factor == 2 {
+                out.copy_from_slice(&[tl, tr, bl, br]);
+            } else {
+                let (b, d, f, h) = (p(0, -1), p(-1, 0), p(1, 0), p(0, 1));
+                out.copy_from_slice(&[
+                    tl,
+                    side(b, d, f),
+                    tr,
+                    side(d, b, h),
+                    e,
+                    side(f, b, h),
+                    bl,
+                    side(h, d, f),
+                    br,
+                ]);
+            }
+        })
+    }
+
+    /// Smoothing in the manner of hqx, with a compact set of rules instead of its lookup
+    /// tables. Colors are compared by their distance in YUV, and a corner cut off by an edge
+    /// between two similar neighbors is blended with them. Produces grays from a black and
+    /// white image.
+    pub fn smooth2x(&self) -> Image {
+        self.smooth(2)
+    }
+
+    /// Like `smooth2x` at a factor of three, where the middle of each side is also slightly
+    /// blended when an edge runs along it.
+    pub fn smooth3x(&self) -> Image {
+        self.smooth(3)
+    }
+
+    /// Hyllian's xBR, level 1, at any integer `factor`. Where the colors change less along a
+    /// corner's anti-diagonal than across it, the corner is cut by an edge through the
+    /// middle of the two sides next to it, and the subpixels under the cut are blended with
+    /// the closer of the two neighbors. Produces grays from a black and white image, and an
+    /// empty one if `factor` is 0.
+    pub fn xbr(&self, factor: usize) -> Image {
+        // The share of each subpixel under the cut, which is `u + v > 1/2` in the coordinates
+        // of the bottom right quadrant, measured on a 4×4 grid.
+        let coverage = (0..factor * factor)
+            .map(|i| {
+                let (sx, sy) = ((i % factor) as f64, (i / factor) as f64);
+                let mut n = 0;
+                for j in 0..16 {
+                    let u = (sx + (j % 4) as f64 / 4. + 0.125) / factor as f64 - 0.5;
+                    let v = (sy + (j / 4) as f64 / 4. + 0.125) / factor as f64 - 0.5;
+                    if u + v > 0.5 {
+                        n += 1;
+                    }
+                }
+                n
+            })
+            .collect::<Vec<u32>>();
+        self.upscale(factor, |x, y, out| {
+            out.fill(self.clamped(x, y));
+            // Each corner is looked at in a frame rotated so that it is the bottom right one.
+            for (sx, sy) in [(1, 1), (-1, 1), (1, -1), (-1, -1)] {
+                let p = |dx: i64, dy: i64| self.clamped(x + sx * dx, y + sy * dy);
+                let (b, c, d, e, f) = (p(0, -1), p(1, -1), p(-1, 0), p(0, 0), p(1, 0));
+                let (g, h, i) = (p(-1, 1), p(0, 1), p(1, 1));
+                let (f4, h5, i4, i5) = (p(2, 0), p(0, 2), p(2, 1), p(1, 2));
+                let along = distance(e, c)
+                    + distance(e, g)
+                    + distance(i, f4)
+                    + distance(i, h5)
+                    + 4. * distance(h, f);
+                let across = distance(h, d)
+                    + distance(h, i5)
+                    + distance(f, b)
+                    + distance(f, i4)
+                    + 4. * distance(e, i);
+                if along >= across {
+                    continue;
+                }
+                let px = if distance(e, f) <= distance(e, h) {
+                    f
+                } else {
+                    h
+                };
+                for (k, share) in coverage.iter().enumerate() {
+                    if *share == 0 {
+                        continue;
+                    }
+                    let (kx, ky) = (k % factor, k / factor);
+                    let kx = if sx > 0 { kx } else { factor - 1 - kx };
+                    let ky = if sy > 0 { ky } else { factor - 1 - ky };
+                    out[ky * factor + kx] = mix(&[(e, 16 - share), (px, *share)]);
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BLACK, WHITE};
+
+    /// An image from rows of `#` for black and `.` for white.
+    fn image(rows: &[&str]) -> Image {
+        let mut ret = Image::new(rows[0].len(), rows.len(), 0, 0);
+        for (y, row) in rows.iter().enumerate() {
+            for (x, c) in row.chars().enumerate() {
+                if c == '#' {
+                    ret.plot(x as i64, y as i64);
+                }
+            }
+        }
+        ret
+    }
+
+    #[test]
+    fn scale2x() {
+        let scaled = image(&["#.", ".#"]).scale2x();
+        assert_eq!(scaled.bytes, image(&["##..", "#.#.", ".#.#", "..##"]).bytes);
+        // The staircase of a diagonal line is rounded off, and an isolated pixel stays square.
+        let scaled = image(&["#...", ".#..", "..#.", "...."]).scale2x();
+        let expected = image(&[
+            "##......", "#.#.....", ".###....", "..###...", "...###..", "....##..", "........",
+            "........",
+        ]);
+        assert_eq!(scaled.bytes, expected.bytes);
+        let dot = image(&["...", ".#.", "..."]);
+        assert_eq!(
+            dot.scale2x().black_pixels(),
+            [(2, 2), (3, 2), (2, 3), (3, 3)]
+        );
+    }
+
+    #[test]
+    fn scale3x_and_4x() {
+        let sprite = image(&["#..#.", ".##..", "#...#"]);
+        let (s3, s4) = (sprite.scale3x(), sprite.scale4x());
+        assert_eq!((s3.width, s3.height), (15, 9));
+        assert_eq!((s4.width, s4.height), (20, 12));
+        // Only the source's colors are used.
+        for scaled in [&s3, &s4] {
+            assert!(scaled.bytes.iter().all(|c| *c == BLACK || *c == WHITE));
+        }
+        let dot = image(&["...", ".#.", "..."]).scale3x();
+        assert_eq!(dot.black_pixels().len(), 9);
+        // The middle of each block keeps the source pixel.
+        for y in 0..3 {
+            for x in 0..5 {
+                assert_eq!(s3.get(3 * x + 1, 3 * y + 1), sprite.get(x, y));
+            }
+        }
+    }
+
+    #[test]
+    fn smooth() {
+        let flat = image(&["###", "###"]);
+        for scaled in [flat.smooth2x(), flat.smooth3x(), flat.xbr(3)] {
+            assert!(scaled.bytes.iter().all(|c| *c == BLACK));
+        }
+        let diagonal = image(&["#...", ".#..", "..#.", "...#"]);
+        for scaled in [diagonal.smooth2x(), diagonal.smooth3x(), diagonal.xbr(2)] {
+            assert!(scaled.bytes.iter().any(|c| *c != BLACK && *c != WHITE));
+            // The pixels far from the line are untouched.
+            assert_eq!(scaled.get(scaled.width as i64 - 1, 0), Some(WHITE));
+            assert_eq!(scaled.get(0, scaled.height as i64 - 1), Some(WHITE));
+        }
+    }
+
+    #[test]
+    fn hotspot() {
+        let mut sprite = image(&["#..", "..."]);
+        sprite.hotspot = Some((2, 1));
+        assert_eq!(sprite.scale2x().hotspot, Some((5, 3)));
+        assert_eq!(sprite.scale3x().hotspot, Some((7, 4)));
+    }
+
+    #[test]
+    fn xbr_factors() {
+        let image = Image::new(3, 2, 0, 0);
+        for factor in 0..4 {
+            let scaled = image.xbr(factor);
+            assert_eq!((scaled.width, scaled.height), (3 * factor, 2 * factor));
+        }
+    }
+}
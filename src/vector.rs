@@ -0,0 +1,429 @@
+//! Typed points, vectors and implicit lines over `i64` or `f64`. Unlike the `(i64, i64)` tuples
+//! used for pixels, `f64` coordinates keep sub-pixel precision until they're rounded to the
+//! pixel grid for plotting.
+
+use std::ops::{Add, AddAssign, Div, Mul, Neg, Sub, SubAssign};
+
+pub trait Scalar:
+    Copy
+    + Default
+    + PartialEq
+    + PartialOrd
+    + std::fmt::Debug
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Div<Output = Self>
+    + Neg<Output = Self>
+{
+    fn zero() -> Self {
+        Self::default()
+    }
+    fn to_f64(self) -> f64;
+    /// Converts from `f64`, rounding to the nearest integer for integer types.
+    fn from_f64(v: f64) -> Self;
+}
+
+impl Scalar for i64 {
+    fn to_f64(self) -> f64 {
+        self as f64
+    }
+    fn from_f64(v: f64) -> Self {
+        v.round() as i64
+    }
+}
+
+impl Scalar for f64 {
+    fn to_f64(self) -> f64 {
+        self
+    }
+    fn from_f64(v: f64) -> Self {
+        v
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Point<T = i64> {
+    pub x: T,
+    pub y: T,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Vector<T = i64> {
+    pub x: T,
+    pub y: T,
+}
+
+/// The line `a * x + b * y + c = 0`. Its normal `(a, b)` points to the positive side.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Line<T = i64> {
+    pub a: T,
+    pub b: T,
+    pub c: T,
+}
+
+impl<T: Scalar> Point<T> {
+    pub const fn new(x: T, y: T) -> Self {
+        Self { x, y }
+    }
+
+    pub fn to_f64(self) -> Point<f64> {
+        Point::new(self.x.to_f64(), self.y.to_f64())
+    }
+
+    /// Rounds to the nearest pixel.
+    pub fn round(self) -> Point<i64> {
+        Point::new(
+            i64::from_f64(self.x.to_f64()),
+            i64::from_f64(self.y.to_f64()),
+        )
+    }
+
+    pub fn distance(self, other: Self) -> f64 {
+        (other - self).length()
+    }
+
+    pub fn midpoint(self, other: Self) -> Point<f64> {
+        let (a, b) = (self.to_f64(), other.to_f64());
+        Point::new((a.x + b.x) / 2., (a.y + b.y) / 2.)
+    }
+}
+
+impl<T: Scalar> Vector<T> {
+    pub const fn new(x: T, y: T) -> Self {
+        Self { x, y }
+    }
+
+    pub fn to_f64(self) -> Vector<f64> {
+        Vector::new(self.x.to_f64(), self.y.to_f64())
+    }
+
+    pub fn round(self) -> Vector<i64> {
+        Vector::new(
+            i64::from_f64(self.x.to_f64()),
+            i64::from_f64(self.y.to_f64()),
+        )
+    }
+
+    pub fn dot(self, other: Self) -> T {
+        self.x * other.x + self.y * other.y
+    }
+
+    /// The z component of the 3D cross product, positive if `other` is clockwise from `self`
+    /// on screen, where y grows downwards.
+    pub fn cross(self, other: Self) -> T {
+        self.x * other.y - self.y * other.x
+    }
+
+    pub fn length_squared(self) -> T {
+        self.dot(self)
+    }
+
+    pub fn length(self) -> f64 {
+        self.length_squared().to_f64().sqrt()
+    }
+
+    /// The unit vector in the same direction, `None` for the zero vector.
+    pub fn normalize(self) -> Option<Vector<f64>> {
+        let len = self.length();
+        if len == 0. {
+            return None;
+        }
+        Some(self.to_f64() / len)
+    }
+
+    /// Rotated by 90°, so that `v.cross(v.perpendicular())` is positive.
+    pub fn perpendicular(self) -> Self {
+        Self::new(-self.y, self.x)
+    }
+}
+
+impl<T: Scalar> Line<T> {
+    pub const fn new(a: T, b: T, c: T) -> Self {
+        Self { a, b, c }
+    }
+
+    /// The line through both points, `None` if they're the same point.
+    pub fn through(p: Point<T>, q: Point<T>) -> Option<Self> {
+        if p == q {
+            return None;
+        }
+        Some(Self::new(p.y - q.y, q.x - p.x, p.x * q.y - q.x * p.y))
+    }
+
+    /// The line of points equidistant from `p` and `q`, `None` if they're the same point.
+    pub fn perp_bisector(p: Point<T>, q: Point<T>) -> Option<Self> {
+        if p == q {
+            return None;
+        }
+        let two = T::from_f64(2.);
+        Some(Self::new(
+            two * (q.x - p.x),
+            two * (q.y - p.y),
+            p.x * p.x + p.y * p.y - q.x * q.x - q.y * q.y,
+        ))
+    }
+
+    pub fn to_f64(self) -> Line<f64> {
+        Line::new(self.a.to_f64(), self.b.to_f64(), self.c.to_f64())
+    }
+
+    pub fn normal(self) -> Vector<T> {
+        Vector::new(self.a, self.b)
+    }
+
+    pub fn direction(self) -> Vector<T> {
+        Vector::new(self.b, -self.a)
+    }
+
+    fn is_degenerate(self) -> bool {
+        self.a == T::zero() && self.b == T::zero()
+    }
+
+    /// `a * x + b * y + c`, zero on the line.
+    pub fn eval(self, p: Point<T>) -> T {
+        self.a * p.x + self.b * p.y + self.c
+    }
+
+    /// Signed distance, positive on the side the normal points to.
+    pub fn distance(self, p: Point<T>) -> Option<f64> {
+        if self.is_degenerate() {
+            return None;
+        }
+        Some(self.eval(p).to_f64() / self.normal().length())
+    }
+
+    /// `None` if the lines are parallel or either of them is degenerate.
+    pub fn intersection(self, other: Self) -> Option<Point<f64>> {
+        let (l1, l2) = (self.to_f64(), other.to_f64());
+        let denom = l1.a * l2.b - l2.a * l1.b;
+        if denom == 0. {
+            return None;
+        }
+        Some(Point::new(
+            (l1.b * l2.c - l2.b * l1.c) / denom,
+            (l2.a * l1.c - l1.a * l2.c) / denom,
+        ))
+    }
+
+    /// The line through `p` perpendicular to this one.
+    pub fn perpendicular(self, p: Point<T>) -> Self {
+        Self::new(self.b, -self.a, self.a * p.y - self.b * p.x)
+    }
+
+    /// The foot of the perpendicular from `p`, i.e. the point of the line closest to `p`.
+    pub fn project(self, p: Point<T>) -> Option<Point<f64>> {
+        let t = self.distance(p)? / self.normal().length();
+        let n = self.normal().to_f64();
+        Some(p.to_f64() - n * t)
+    }
+
+    /// The reflection of `p` across the line.
+    pub fn mirror(self, p: Point<T>) -> Option<Point<f64>> {
+        let foot = self.project(p)?;
+        Some(foot + (foot - p.to_f64()))
+    }
+
+    /// The angle between the lines, in radians.
+    pub fn angle(self, other: Self) -> Option<f64> {
+        if self.is_degenerate() || other.is_degenerate() {
+            return None;
+        }
+        let (n1, n2) = (self.normal(), other.normal());
+        let cos = n1.dot(n2).to_f64() / (n1.length() * n2.length());
+        Some(cos.clamp(-1., 1.).acos())
+    }
+}
+
+/// The center and radius of the circle through three points, `None` if they're collinear.
+pub fn circle_through<T: Scalar>(
+    p: Point<T>,
+    q: Point<T>,
+    r: Point<T>,
+) -> Option<(Point<f64>, f64)> {
+    let center = Line::perp_bisector(p, q)?.intersection(Line::perp_bisector(q, r)?)?;
+    Some((center, center.distance(p.to_f64())))
+}
+
+impl<T> From<(T, T)> for Point<T> {
+    fn from((x, y): (T, T)) -> Self {
+        Self { x, y }
+    }
+}
+
+impl<T> From<Point<T>> for (T, T) {
+    fn from(p: Point<T>) -> Self {
+        (p.x, p.y)
+    }
+}
+
+impl<T> From<(T, T)> for Vector<T> {
+    fn from((x, y): (T, T)) -> Self {
+        Self { x, y }
+    }
+}
+
+impl<T> From<Vector<T>> for (T, T) {
+    fn from(v: Vector<T>) -> Self {
+        (v.x, v.y)
+    }
+}
+
+impl<T> From<(T, T, T)> for Line<T> {
+    fn from((a, b, c): (T, T, T)) -> Self {
+        Self { a, b, c }
+    }
+}
+
+impl<T> From<Line<T>> for (T, T, T) {
+    fn from(l: Line<T>) -> Self {
+        (l.a, l.b, l.c)
+    }
+}
+
+impl<T: Scalar> Sub for Point<T> {
+    type Output = Vector<T>;
+    fn sub(self, other: Self) -> Vector<T> {
+        Vector::new(self.x - other.x, self.y - other.y)
+    }
+}
+
+impl<T: Scalar> Add<Vector<T>> for Point<T> {
+    type Output = Self;
+    fn add(self, v: Vector<T>) -> Self {
+        Self::new(self.x + v.x, self.y + v.y)
+    }
+}
+
+impl<T: Scalar> Sub<Vector<T>> for Point<T> {
+    type Output = Self;
+    fn sub(self, v: Vector<T>) -> Self {
+        Self::new(self.x - v.x, self.y - v.y)
+    }
+}
+
+impl<T: Scalar> AddAssign<Vector<T>> for Point<T> {
+    fn add_assign(&mut self, v: Vector<T>) {
+        *self = *self + v;
+    }
+}
+
+impl<T: Scalar> SubAssign<Vector<T>> for Point<T> {
+    fn sub_assign(&mut self, v: Vector<T>) {
+        *self = *self - v;
+    }
+}
+
+impl<T: Scalar> Add for Vector<T> {
+    type Output = Self;
+    fn add(self, other: Self) -> Self {
+        Self::new(self.x + other.x, self.y + other.y)
+    }
+}
+
+impl<T: Scalar> Sub for Vector<T> {
+    type Output = Self;
+    fn sub(self, other: Self) -> Self {
+        Self::new(self.x - other.x, self.y - other.y)
+    }
+}
+
+impl<T: Scalar> AddAssign for Vector<T> {
+    fn add_assign(&mut self, other: Self) {
+        *self = *self + other;
+    }
+}
+
+impl<T: Scalar> SubAssign for Vector<T> {
+    fn sub_assign(&mut self, other: Self) {
+        *self = *self - other;
+    }
+}
+
+impl<T: Scalar> Neg for Vector<T> {
+    type Output = Self;
+    fn neg(self) -> Self {
+        Self::new(-self.x, -self.y)
+    }
+}
+
+impl<T: Scalar> Mul<T> for Vector<T> {
+    type Output = Self;
+    fn mul(self, s: T) -> Self {
+        Self::new(self.x * s, self.y * s)
+    }
+}
+
+impl<T: Scalar> Div<T> for Vector<T> {
+    type Output = Self;
+    fn div(self, s: T) -> Self {
+        Self::new(self.x / s, self.y / s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(p: Point<f64>, (x, y): (f64, f64)) -> bool {
+        (p.x - x).abs() < 1e-9 && (p.y - y).abs() < 1e-9
+    }
+
+    #[test]
+    fn arithmetic() {
+        let (p, q) = (Point::new(1, 2), Point::new(4, 6));
+        let v = q - p;
+        assert_eq!(v, Vector::new(3, 4));
+        assert_eq!(p + v, q);
+        assert_eq!(v.length(), 5.);
+        assert_eq!(p.distance(q), 5.);
+        assert_eq!(p.midpoint(q), Point::new(2.5, 4.));
+        assert_eq!(v.perpendicular(), Vector::new(-4, 3));
+        assert!(v.cross(v.perpendicular()) > 0);
+        assert_eq!(v.dot(v.perpendicular()), 0);
+        assert_eq!(Vector::new(0., 0.).normalize(), None);
+        assert_eq!(Vector::new(0., -2.).normalize(), Some(Vector::new(0., -1.)));
+        assert_eq!(Point::new(2.5, -1.5).round(), Point::new(3, -2));
+    }
+
+    #[test]
+    fn lines() {
+        let diagonal = Line::through(Point::new(0, 0), Point::new(4, 4)).unwrap();
+        assert_eq!(diagonal.eval(Point::new(7, 7)), 0);
+        assert!(Line::through(Point::new(1, 1), Point::new(1, 1)).is_none());
+
+        let anti = Line::new(1., 1., -10.);
+        let meet = diagonal.to_f64().intersection(anti).unwrap();
+        assert!(close(meet, (5., 5.)));
+        assert!(diagonal.intersection(Line::new(2, -2, 7)).is_none());
+        assert!(
+            (diagonal.to_f64().angle(anti).unwrap() - std::f64::consts::FRAC_PI_2).abs() < 1e-12
+        );
+
+        // Sub-pixel results aren't rounded.
+        let x_axis = Line::new(0., 1., 0.);
+        assert!(close(
+            x_axis.project(Point::new(2.5, 3.25)).unwrap(),
+            (2.5, 0.)
+        ));
+        assert!(close(
+            x_axis.mirror(Point::new(2.5, 3.25)).unwrap(),
+            (2.5, -3.25)
+        ));
+        assert_eq!(x_axis.distance(Point::new(0., -1.5)), Some(-1.5));
+        let perpendicular = x_axis.perpendicular(Point::new(2.5, 1.));
+        assert_eq!(perpendicular.eval(Point::new(2.5, 7.)), 0.);
+        assert_eq!(Line::new(0., 0., 1.).distance(Point::new(0., 0.)), None);
+    }
+
+    #[test]
+    fn circle() {
+        let (center, radius) =
+            circle_through(Point::new(0, 0), Point::new(6, 0), Point::new(0, 8)).unwrap();
+        assert!(close(center, (3., 4.)));
+        assert!((radius - 5.).abs() < 1e-12);
+        let bisector = Line::perp_bisector(Point::new(0., 0.), Point::new(6., 0.)).unwrap();
+        assert_eq!(bisector.eval(Point::new(3., 17.)), 0.);
+        assert!(circle_through(Point::new(0, 0), Point::new(1, 1), Point::new(2, 2)).is_none());
+    }
+}
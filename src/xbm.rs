@@ -0,0 +1,578 @@
+use crate::{bits_to_bytes, bytes_to_bits, Image};
+use std::fs::File;
+use std::io::prelude::*;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum XbmErrorKind {
+    UnexpectedChar(char),
+    UnterminatedComment,
+    UnexpectedToken {
+        expected: &'static str,
+        found: String,
+    },
+    UnexpectedEof {
+        expected: &'static str,
+    },
+    MissingDefine(&'static str),
+    InvalidNumber(String),
+    ValueOutOfRange {
+        value: u64,
+        max: u64,
+    },
+    WrongLength {
+        expected: usize,
+        found: usize,
+    },
+}
+
+/// A parse error, positioned at the 1-based `line` and `column` of the offending token.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct XbmError {
+    pub kind: XbmErrorKind,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl std::fmt::Display for XbmError {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(fmt, "xbm:{}:{}: ", self.line, self.column)?;
+        match &self.kind {
+            XbmErrorKind::UnexpectedChar(c) => write!(fmt, "unexpected character {:?}", c),
+            XbmErrorKind::UnterminatedComment => write!(fmt, "unterminated comment"),
+            XbmErrorKind::UnexpectedToken { expected, found } => {
+                write!(fmt, "expected {}, found `{}`", expected, found)
+            }
+            XbmErrorKind::UnexpectedEof { expected } => {
+                write!(fmt, "expected {}, found end of file", expected)
+            }
+            XbmErrorKind::MissingDefine(suffix) => {
+                write!(fmt, "missing `#define <name>_{}`", suffix)
+            }
+            XbmErrorKind::InvalidNumber(n) => write!(fmt, "invalid number `{}`", n),
+            XbmErrorKind::ValueOutOfRange { value, max } => {
+                write!(
+                    fmt,
+                    "value {} does not fit the array type (max {})",
+                    value, max
+                )
+            }
+            XbmErrorKind::WrongLength { expected, found } => write!(
+                fmt,
+                "bits array has {} values but width and height need {}",
+                found, expected
+            ),
+        }
+    }
+}
+
+impl std::error::Error for XbmError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Hash,
+    Ident(String),
+    Number(u64),
+    Punct(char),
+    Eof,
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Token::Hash => write!(fmt, "#"),
+            Token::Ident(i) => write!(fmt, "{}", i),
+            Token::Number(n) => write!(fmt, "{}", n),
+            Token::Punct(c) => write!(fmt, "{}", c),
+            Token::Eof => write!(fmt, "end of file"),
+        }
+    }
+}
+
+fn tokenize(s: &str) -> Result<Vec<(Token, usize, usize)>, XbmError> {
+    let mut ret = vec![];
+    let mut chars = s.chars().peekable();
+    let (mut line, mut column) = (1, 1);
+    let error = |kind, line, column| XbmError { kind, line, column };
+    while let Some(&c) = chars.peek() {
+        let (start_line, start_column) = (line, column);
+        let mut bump = |chars: &mut std::iter::Peekable<std::str::Chars>| {
+            let c = chars.next();
+            if c == Some('\n') {
+                line += 1;
+                column = 1;
+            } else {
+                column += 1;
+            }
+            c
+        };
+        match c {
+            _ if c.is_whitespace() => {
+                bump(&mut chars);
+            }
+            '/' => {
+                bump(&mut chars);
+                match chars.peek() {
+                    Some('*') => {
+                        bump(&mut chars);
+                        let mut prev = ' ';
+                        loop {
+                            match bump(&mut chars) {
+                                Some('/') if prev == '*' => break,
+                                Some(c) => prev = c,
+                                None => {
+                                    return Err(error(
+                                        XbmErrorKind::UnterminatedComment,
+                                        start_line,
+                                        start_column,
+                                    ))
+                                }
+                            }
+                        }
+                    }
+                    Some('/') => {
+                        while !matches!(chars.peek(), Some('\n') | None) {
+                            bump(&mut chars);
+                        }
+                    }
+                    _ => {
+                        return Err(error(
+                            XbmErrorKind::UnexpectedChar('/'),
+                            start_line,
+                            start_column,
+                        ))
+                    }
+                }
+            }
+            '#' => {
+                bump(&mut chars);
+                ret.push((Token::Hash, start_line, start_column));
+            }
+            '[' | ']' | '{' | '}' | '=' | ',' | ';' => {
+                bump(&mut chars);
+                ret.push((Token::Punct(c), start_line, start_column));
+            }
+            _ if c.is_ascii_alphabetic() || c == '_' => {
+                let mut ident = String::new();
+                while let Some(&c) = chars.peek() {
+                    if !(c.is_ascii_alphanumeric() || c == '_') {
+                        break;
+                    }
+                    ident.push(c);
+                    bump(&mut chars);
+                }
+                ret.push((Token::Ident(ident), start_line, start_column));
+            }
+            _ if c.is_ascii_digit() => {
+                let mut literal = String::new();
+                while let Some(&c) = chars.peek() {
+                    if !c.is_ascii_alphanumeric() {
+                        break;
+                    }
+                    literal.push(c);
+                    bump(&mut chars);
+                }
+                let value = if let Some(hex) = literal
+                    .strip_prefix("0x")
+                    .or_else(|| literal.strip_prefix("0X"))
+                {
+                    u64::from_str_radix(hex, 16)
+                } else {
+                    literal.parse::<u64>()
+                };
+                match value {
+                    Ok(value) => ret.push((Token::Number(value), start_line, start_column)),
+                    Err(_) => {
+                        return Err(error(
+                            XbmErrorKind::InvalidNumber(literal),
+                            start_line,
+                            start_column,
+                        ))
+                    }
+                }
+            }
+            other => {
+                return Err(error(
+                    XbmErrorKind::UnexpectedChar(other),
+                    start_line,
+                    start_column,
+                ))
+            }
+        }
+    }
+    ret.push((Token::Eof, line, column));
+    Ok(ret)
+}
+
+struct Parser {
+    tokens: Vec<(Token, usize, usize)>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> &Token {
+        &self.tokens[self.pos].0
+    }
+
+    fn at_eof(&self) -> bool {
+        *self.peek() == Token::Eof
+    }
+
+    fn error(&self, kind: XbmErrorKind) -> XbmError {
+        self.error_at(self.pos, kind)
+    }
+
+    fn error_at(&self, pos: usize, kind: XbmErrorKind) -> XbmError {
+        let (_, line, column) = self.tokens[pos];
+        XbmError { kind, line, column }
+    }
+
+    fn unexpected(&self, expected: &'static str) -> XbmError {
+        if self.at_eof() {
+            self.error(XbmErrorKind::UnexpectedEof { expected })
+        } else {
+            self.error(XbmErrorKind::UnexpectedToken {
+                expected,
+                found: self.peek().to_string(),
+            })
+        }
+    }
+
+    fn next(&mut self) -> Token {
+        let ret = self.peek().clone();
+        if !self.at_eof() {
+            self.pos += 1;
+        }
+        ret
+    }
+
+    fn expect_punct(&mut self, c: char, expected: &'static str) -> Result<(), XbmError> {
+        if *self.peek() != Token::Punct(c) {
+            return Err(self.unexpected(expected));
+        }
+        self.next();
+        Ok(())
+    }
+
+    fn ident(&mut self, expected: &'static str) -> Result<String, XbmError> {
+        match self.peek() {
+            Token::Ident(_) => match self.next() {
+                Token::Ident(i) => Ok(i),
+                _ => unreachable!(),
+            },
+            _ => Err(self.unexpected(expected)),
+        }
+    }
+
+    fn number(&mut self, expected: &'static str) -> Result<u64, XbmError> {
+        match *self.peek() {
+            Token::Number(n) => {
+                self.next();
+                Ok(n)
+            }
+            _ => Err(self.unexpected(expected)),
+        }
+    }
+}
+
+/// The contents of an X11 or X10 bitmap file.
+///
+/// `bits` is always in the X11 layout that `bits_to_bytes` takes: rows padded to whole bytes,
+/// least significant bit first. X10 files, which store `short`s with rows padded to 16 bits, are
+/// converted on parsing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Xbm {
+    pub name: String,
+    pub width: usize,
+    pub height: usize,
+    pub hotspot: Option<(usize, usize)>,
+    pub bits: Vec<u8>,
+}
+
+impl Xbm {
+    pub fn parse(s: &str) -> Result<Self, XbmError> {
+        let mut p = Parser {
+            tokens: tokenize(s)?,
+            pos: 0,
+        };
+        let (mut width, mut height, mut x_hot, mut y_hot) = (None, None, None, None);
+        let mut name = None;
+        let mut array = None;
+        while !p.at_eof() {
+            if *p.peek() == Token::Hash {
+                p.next();
+                let directive = p.ident("preprocessor directive")?;
+                if directive != "define" {
+                    return Err(p.unexpected("`define`"));
+                }
+                let ident = p.ident("macro name")?;
+                let value = p.number("macro value")? as usize;
+                if let Some(prefix) = ident.strip_suffix("_width") {
+                    name.get_or_insert_with(|| prefix.to_string());
+                    width = Some(value);
+                } else if ident.ends_with("_height") {
+                    height = Some(value);
+                } else if ident.ends_with("_x_hot") {
+                    x_hot = Some(value);
+                } else if ident.ends_with("_y_hot") {
+                    y_hot = Some(value);
+                }
+                continue;
+            }
+            if array.is_some() {
+                return Err(p.unexpected("end of file"));
+            }
+            array = Some((p.pos, Self::parse_array(&mut p)?));
+        }
+        let (array_pos, (is_short, bits)) = array.ok_or_else(|| p.unexpected("bits array"))?;
+        let width = width.ok_or_else(|| p.error(XbmErrorKind::MissingDefine("width")))?;
+        let height = height.ok_or_else(|| p.error(XbmErrorKind::MissingDefine("height")))?;
+        let hotspot = x_hot.zip(y_hot);
+
+        let bytes_per_row = width.div_ceil(8);
+        let bits = if is_short {
+            let shorts_per_row = width.div_ceil(16);
+            // Dimensions too large to multiply can't match any array either.
+            let expected = shorts_per_row.saturating_mul(height);
+            if bits.len() != expected {
+                return Err(p.error_at(
+                    array_pos,
+                    XbmErrorKind::WrongLength {
+                        expected,
+                        found: bits.len(),
+                    },
+                ));
+            }
+            let mut ret = Vec::with_capacity(bytes_per_row * height);
+            for row in bits.chunks(shorts_per_row.max(1)).take(height) {
+                let row = row
+                    .iter()
+                    .flat_map(|s| (*s as u16).to_le_bytes())
+                    .collect::<Vec<u8>>();
+                ret.extend_from_slice(&row[..bytes_per_row]);
+            }
+            ret
+        } else {
+            let expected = bytes_per_row.saturating_mul(height);
+            if bits.len() != expected {
+                return Err(p.error_at(
+                    array_pos,
+                    XbmErrorKind::WrongLength {
+                        expected,
+                        found: bits.len(),
+                    },
+                ));
+            }
+            bits.into_iter().map(|b| b as u8).collect()
+        };
+        Ok(Xbm {
+            name: name.unwrap_or_default(),
+            width,
+            height,
+            hotspot,
+            bits,
+        })
+    }
+
+    /// `[static] [const] [unsigned] char|short <name>_bits[] = { ... };`
+    fn parse_array(p: &mut Parser) -> Result<(bool, Vec<u64>), XbmError> {
+        let is_short = loop {
+            let is_short = match p.peek() {
+                Token::Ident(i)
+                    if matches!(i.as_str(), "static" | "const" | "unsigned" | "signed") =>
+                {
+                    None
+                }
+                Token::Ident(i) if i == "char" => Some(false),
+                Token::Ident(i) if i == "short" => Some(true),
+                _ => return Err(p.unexpected("`char` or `short` array declaration")),
+            };
+            p.next();
+            if let Some(is_short) = is_short {
+                break is_short;
+            }
+        };
+        let max = if is_short { u16::MAX } else { u8::MAX as u16 } as u64;
+        p.ident("array name")?;
+        p.expect_punct('[', "`[`")?;
+        if let Token::Number(_) = p.peek() {
+            p.next();
+        }
+        p.expect_punct(']', "`]`")?;
+        p.expect_punct('=', "`=`")?;
+        p.expect_punct('{', "`{`")?;
+        let mut values = vec![];
+        while *p.peek() != Token::Punct('}') {
+            let pos = p.pos;
+            let value = p.number("array value")?;
+            if value > max {
+                return Err(p.error_at(pos, XbmErrorKind::ValueOutOfRange { value, max }));
+            }
+            values.push(value);
+            if *p.peek() == Token::Punct(',') {
+                p.next();
+            } else if *p.peek() != Token::Punct('}') {
+                return Err(p.unexpected("`,` or `}`"));
+            }
+        }
+        p.next();
+        if *p.peek() == Token::Punct(';') {
+            p.next();
+        }
+        Ok((is_short, values))
+    }
+
+    pub fn to_image(&self, x_offset: usize, y_offset: usize) -> Image {
+        let mut ret = Image::new(self.width, self.height, x_offset, y_offset);
+        ret.bytes = bits_to_bytes(&self.bits, self.width);
+        ret.hotspot = self.hotspot;
+        ret
+    }
+}
+
+impl Image {
+    pub fn from_xbm(
+        path: &str,
+        x_offset: usize,
+        y_offset: usize,
+    ) -> Result<Self, Box<dyn ::std::error::Error>> {
+        let mut file = File::open(path)?;
+        let mut s = String::new();
+        file.read_to_string(&mut s)?;
+        Ok(Xbm::parse(&s)?.to_image(x_offset, y_offset))
+    }
+
+    /// Encodes the image as an X11 bitmap with `name` as the C identifier prefix, in the same
+    /// layout the `bitmap` program writes. The hotspot is written if the image has one.
+    pub fn to_xbm(&self, name: &str) -> String {
+        let mut ret = format!(
+            "#define {name}_width {}\n#define {name}_height {}\n",
+            self.width,
+            self.height,
+            name = name
+        );
+        if let Some((x_hot, y_hot)) = self.hotspot {
+            ret.push_str(&format!(
+                "#define {name}_x_hot {}\n#define {name}_y_hot {}\n",
+                x_hot,
+                y_hot,
+                name = name
+            ));
+        }
+        ret.push_str(&format!("static unsigned char {}_bits[] = {{", name));
+        let bits = bytes_to_bits(&self.bytes, self.width);
+        for (i, b) in bits.iter().enumerate() {
+            if i % 12 == 0 {
+                ret.push_str("\n  ");
+            }
+            ret.push_str(&format!(" 0x{:02x}", b));
+            if i + 1 != bits.len() {
+                ret.push(',');
+            }
+        }
+        ret.push_str("};\n");
+        ret
+    }
+
+    pub fn save_xbm(&self, path: &str, name: &str) -> Result<(), Box<dyn ::std::error::Error>> {
+        File::create(path)?.write_all(self.to_xbm(name).as_bytes())?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BLACK, WHITE};
+
+    fn kind(s: &str) -> XbmErrorKind {
+        Xbm::parse(s).err().unwrap().kind
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut image = Image::new(13, 3, 0, 0);
+        for (i, p) in image.bytes.iter_mut().enumerate() {
+            *p = if i % 5 == 0 || i % 7 == 0 {
+                BLACK
+            } else {
+                WHITE
+            };
+        }
+        image.hotspot = Some((4, 2));
+        let xbm = Xbm::parse(&image.to_xbm("test")).unwrap();
+        assert_eq!(xbm.name, "test");
+        assert_eq!((xbm.width, xbm.height), (13, 3));
+        let decoded = xbm.to_image(0, 0);
+        assert_eq!(decoded.bytes, image.bytes);
+        assert_eq!(decoded.hotspot, Some((4, 2)));
+    }
+
+    #[test]
+    fn shorts_and_comments() {
+        let xbm = Xbm::parse(
+            "/* X10 style */\n#define s_width 16 // one row\n#define s_height 1\n\
+             static short s_bits[] = { 0x8001, };",
+        )
+        .unwrap();
+        let image = xbm.to_image(0, 0);
+        let black = image.bytes.iter().map(|p| *p == BLACK).collect::<Vec<_>>();
+        assert!(black[0] && black[15]);
+        assert_eq!(black.iter().filter(|b| **b).count(), 2);
+    }
+
+    #[test]
+    fn malformed() {
+        let header = "#define a_width 8\n#define a_height 2\n";
+        assert!(matches!(
+            kind(&format!(
+                "{}static char a_bits[] = {{ 0xZZ, 0x00 }};",
+                header
+            )),
+            XbmErrorKind::InvalidNumber(_)
+        ));
+        assert_eq!(
+            kind(&format!("{}static char a_bits[] = {{ 0x00 }};", header)),
+            XbmErrorKind::WrongLength {
+                expected: 2,
+                found: 1
+            }
+        );
+        assert_eq!(
+            kind(&format!(
+                "{}static char a_bits[] = {{ 0x100, 0x00 }};",
+                header
+            )),
+            XbmErrorKind::ValueOutOfRange {
+                value: 0x100,
+                max: 0xff
+            }
+        );
+        assert_eq!(
+            kind("#define a_width 8\nstatic char a_bits[] = { 0x00 };"),
+            XbmErrorKind::MissingDefine("height")
+        );
+        assert_eq!(
+            kind(&format!("{}/* static char", header)),
+            XbmErrorKind::UnterminatedComment
+        );
+        assert!(matches!(
+            kind(&format!("{}static char a_bits[] = {{ 0x00, 0x00", header)),
+            XbmErrorKind::UnexpectedEof { .. }
+        ));
+    }
+
+    #[test]
+    fn oversize_dimensions() {
+        for array in ["char", "short"] {
+            let s = format!(
+                "#define a_width 18446744073709551615\n#define a_height 18446744073709551615\n\
+                 static {} a_bits[] = {{ 0x00 }};",
+                array
+            );
+            assert!(matches!(
+                kind(&s),
+                XbmErrorKind::WrongLength { found: 1, .. }
+            ));
+        }
+    }
+}